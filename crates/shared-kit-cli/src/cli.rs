@@ -1,6 +1,10 @@
 use crate::{
     config::Config,
-    subcommand::new_command::{NewCommand, new_command_action},
+    subcommand::{
+        cache_command::{CacheCommand, cache_command_action},
+        new_command::{NewCommand, new_command_action},
+        template_command::{TemplateCommand, template_command_action},
+    },
 };
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
@@ -27,16 +31,23 @@ struct SharedKitCli {
 #[derive(Subcommand)]
 enum Commands {
     New(NewCommand),
+    /// List, inspect and manage configured templates
+    Template(TemplateCommand),
+    /// Inspect and clean the download cache of remote templates
+    Cache(CacheCommand),
 }
 
 pub fn run_cli() -> Result<()> {
     crate::helper::logger::init_logger();
 
     let cli = SharedKitCli::parse();
-    let mut config =
-        Config::from_path(cli.config).with_context(|| format!("Failed to load CLI config"))?;
+    let load_config =
+        || Config::from_path(cli.config.clone()).with_context(|| "Failed to load CLI config");
 
     match &cli.command {
-        Commands::New(args) => new_command_action(&mut config, args),
+        Commands::New(args) => new_command_action(&mut load_config()?, args),
+        Commands::Template(args) => template_command_action(&mut load_config()?, args),
+        // The cache does not use the config, so a broken config file must not block it
+        Commands::Cache(args) => cache_command_action(args),
     }
 }
@@ -1,2 +1,2 @@
+pub mod new_command;
 pub mod progress;
-pub mod new_command;
\ No newline at end of file
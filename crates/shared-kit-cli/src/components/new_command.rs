@@ -1,12 +1,16 @@
 use anyhow::{Context, Ok};
 use atty::Stream;
-use inquire::{Select, Text};
+use inquire::validator::Validation;
+use inquire::{Confirm, CustomType, MultiSelect, Select, Text};
+use regex::Regex;
 use std::fmt::Display;
-use std::io::{self, BufRead};
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
-use crate::config::Config;
-use crate::constant::TemplateItem;
+use crate::config::{Config, load_answers};
+use crate::constant::{OnExistsAction, TemplateItem, TemplateVar, TemplateVarValues, VarType};
+use crate::helper::computed::compute_value;
+use crate::helper::path::expand_dir;
+use crate::helper::template_engine::{self, Value};
 use crate::subcommand::new_command::NewCommand;
 
 #[derive(Debug, Clone)]
@@ -27,7 +31,57 @@ impl Display for TargetDirExistAction {
     }
 }
 
-pub fn ensure_target_directory(mut target: PathBuf) -> anyhow::Result<PathBuf> {
+/// Whether prompts can be shown, i.e. stdin is attached to a terminal.
+pub fn is_interactive() -> bool {
+    atty::is(Stream::Stdin)
+}
+
+/// The directory a project is generated into.
+#[derive(Debug, Clone)]
+pub struct TargetDir {
+    pub path: PathBuf,
+    /// The existing directory is replaced, only once the new project was generated
+    pub replace: bool,
+}
+
+/// Checks that a project name is a single directory name, so it cannot point at the current
+/// directory, a parent or somewhere else on disk.
+pub fn check_project_name(name: &str) -> anyhow::Result<()> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(()),
+        _ => anyhow::bail!(
+            "❌ Invalid project name '{}': expected a single directory name without '/', '.' or '..'",
+            name
+        ),
+    }
+}
+
+/// Decides where the project goes when the target already exists.
+///
+/// Nothing is removed here: an overwritten directory is only replaced after generation
+/// succeeded, see [`TargetDir::replace`].
+pub fn ensure_target_directory(
+    mut target: PathBuf,
+    on_exists: Option<&OnExistsAction>,
+) -> anyhow::Result<TargetDir> {
+    if target.exists() {
+        match on_exists {
+            Some(OnExistsAction::Overwrite) => {
+                return Ok(TargetDir { path: target, replace: true });
+            }
+            Some(OnExistsAction::Merge) => return Ok(TargetDir { path: target, replace: false }),
+            Some(OnExistsAction::Fail) => {
+                anyhow::bail!("❌ Target directory already exists: '{}'", target.display())
+            }
+            None if !is_interactive() => anyhow::bail!(
+                "❌ Target directory already exists: '{}'. Pass --on-exists overwrite|fail|merge when running without a terminal.",
+                target.display()
+            ),
+            None => {}
+        }
+    }
+
     while target.exists() {
         let choices = vec![
             TargetDirExistAction::Rename,
@@ -45,14 +99,13 @@ pub fn ensure_target_directory(mut target: PathBuf) -> anyhow::Result<PathBuf> {
                 let new_name = Text::new("Please enter a new project name:")
                     .prompt()
                     .with_context(|| "Failed to read new project name")?;
+                check_project_name(&new_name)?;
                 target = std::env::current_dir()
                     .with_context(|| "Failed to get current directory")?
                     .join(new_name);
             }
             TargetDirExistAction::Overwrite => {
-                std::fs::remove_dir_all(&target)
-                    .with_context(|| format!("Failed to remove directory: {}", target.display()))?;
-                break;
+                return Ok(TargetDir { path: target, replace: true });
             }
             TargetDirExistAction::Cancel => {
                 anyhow::bail!("Operation canceled by user.");
@@ -60,13 +113,13 @@ pub fn ensure_target_directory(mut target: PathBuf) -> anyhow::Result<PathBuf> {
         }
     }
 
-    Ok(target)
+    Ok(TargetDir { path: target, replace: false })
 }
 
 pub fn ensure_template_selected(
     config: &Config,
     args: &NewCommand,
-) -> anyhow::Result<TemplateItem> {
+) -> anyhow::Result<(String, TemplateItem)> {
     let available_templates = config.metadata.get_templates(args.kind.clone());
 
     if available_templates.is_empty() {
@@ -82,31 +135,301 @@ pub fn ensure_template_selected(
         );
     }
 
-    let options: Vec<String> = available_templates.keys().map(|name| name.to_string()).collect();
-
-    let selected = if atty::is(Stream::Stdin) {
-        // 正常交互
-        inquire::Select::new("📦 Select a template to use", options.clone())
-            .prompt()
-            .with_context(|| "Failed to select a template")?
-    } else {
-        // 非交互测试：从 stdin 模拟读取
-        let stdin = io::stdin();
-        let mut lines = stdin.lock().lines();
-        let input = lines
-            .next()
-            .transpose()
-            .context("Failed to read simulated input from stdin")?
-            .unwrap_or_default();
-        if !options.contains(&input) {
-            anyhow::bail!("Invalid simulated input: '{}'", input);
+    let mut options: Vec<String> =
+        available_templates.keys().map(|name| name.to_string()).collect();
+    options.sort();
+
+    let selected = match &args.template_name {
+        Some(name) => name.clone(),
+        None if is_interactive() => {
+            inquire::Select::new("📦 Select a template to use", options.clone())
+                .prompt()
+                .with_context(|| "Failed to select a template")?
+        }
+        None => anyhow::bail!(
+            "❌ No template selected. Pass --template-name <name> when running without a terminal. Available templates: {}",
+            options.join(", ")
+        ),
+    };
+
+    let template = available_templates.get(&selected).with_context(|| {
+        format!(
+            "Template '{}' not found in config. Available templates: {}",
+            selected,
+            options.join(", ")
+        )
+    })?;
+
+    Ok((selected, template.clone()))
+}
+
+/// Collects a value for every template variable.
+///
+/// Values come from `--var`, then the `--answers` file, then the default when `--yes` is set,
+/// and only then from an interactive prompt matching the variable's type. Every value is
+/// checked against the type, `choices` and `validate`. Variables whose `when` condition is
+/// false for the earlier answers are not asked and left empty (`false` for bools).
+///
+/// Variables with `compute` are evaluated after all others have been asked, in the order they
+/// are defined, unless `--var` or `--answers` already set them. A `when` condition can only
+/// use variables evaluated before it, so asked variables cannot depend on computed ones;
+/// such conditions are rejected before anything is asked.
+pub fn ensure_template_vars(
+    vars: &[TemplateVar],
+    args: &NewCommand,
+) -> anyhow::Result<TemplateVarValues> {
+    let mut presets = match &args.answers {
+        Some(path) => {
+            let path = expand_dir(path)
+                .with_context(|| format!("Failed to expand answers path: {}", path))?;
+            load_answers(&path)?
+        }
+        None => TemplateVarValues::new(),
+    };
+    presets.extend(args.vars.iter().cloned());
+
+    let prompted = vars.iter().filter(|var| var.compute.is_none());
+    let computed = vars.iter().filter(|var| var.compute.is_some());
+    let order: Vec<&TemplateVar> = prompted.chain(computed).collect();
+    check_when_order(&order)?;
+
+    let mut values = TemplateVarValues::new();
+
+    for var in order {
+        let name = var.name().to_string();
+        let pattern = var_pattern(var)?;
+
+        if let Some(when) = &var.when {
+            let holds =
+                template_engine::evaluate(when, &answers_context(&values)).map_err(|err| {
+                    anyhow::anyhow!(
+                        "❌ Invalid condition '{}' for '{}': {}",
+                        when,
+                        name,
+                        err.message
+                    )
+                })?;
+            if !holds.is_truthy() {
+                let empty = if var.var_type == VarType::Bool { "false" } else { "" };
+                values.insert(name, empty.to_string());
+                continue;
+            }
+        }
+
+        let value = if let Some(value) = presets.get(&name) {
+            value.clone()
+        } else if let Some(compute) = &var.compute {
+            compute_value(compute, &answers_context(&values))
+                .with_context(|| format!("Failed to compute '{}'", name))?
+        } else if let (true, Some(default)) = (args.yes, &var.default) {
+            default.clone()
+        } else if !is_interactive() {
+            anyhow::bail!(
+                "❌ Missing value for template variable '{}'. Pass --var {}=<value>, an --answers file or --yes to use defaults.",
+                name,
+                name
+            );
+        } else {
+            prompt_var(var, &name, pattern.as_ref())
+                .with_context(|| format!("Failed to read value for '{}'", name))?
+        };
+
+        let value = check_var_value(var, pattern.as_ref(), &value).map_err(|err| {
+            anyhow::anyhow!(
+                "❌ Invalid value '{}' for template variable '{}': {}",
+                value,
+                name,
+                err
+            )
+        })?;
+        values.insert(name, value);
+    }
+
+    Ok(values)
+}
+
+/// Compiles the `validate` pattern and checks that choice variables have choices.
+fn var_pattern(var: &TemplateVar) -> anyhow::Result<Option<Regex>> {
+    if matches!(var.var_type, VarType::Select | VarType::Multiselect) && var.choices.is_empty() {
+        anyhow::bail!(
+            "❌ Template variable '{}' of type {} needs choices",
+            var.name(),
+            var.var_type
+        );
+    }
+
+    var.validate
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .with_context(|| format!("Invalid validate pattern for '{}'", var.name()))
+}
+
+/// Checks that every `when` condition only uses variables evaluated before its own.
+fn check_when_order(order: &[&TemplateVar]) -> anyhow::Result<()> {
+    for (index, var) in order.iter().enumerate() {
+        let Some(when) = &var.when else {
+            continue;
+        };
+        let invalid = |message: String| {
+            anyhow::anyhow!("❌ Invalid condition '{}' for '{}': {}", when, var.name(), message)
+        };
+
+        let used = template_engine::variables(when).map_err(|err| invalid(err.message))?;
+        let later = used.iter().find(|name| order[index..].iter().any(|v| v.name() == *name));
+        if let Some(later) = later {
+            return Err(invalid(format!(
+                "'{}' is evaluated after it, conditions can only use variables asked before them and computed variables are evaluated last",
+                later
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_message(var: &TemplateVar, pattern: &Regex) -> String {
+    var.validate_message.clone().unwrap_or_else(|| format!("must match '{}'", pattern.as_str()))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "y" | "on" | "1" => Some(true),
+        "false" | "no" | "n" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn split_choices(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty())
+}
+
+/// Checks a value against the variable's type, `choices` and `validate` pattern.
+///
+/// Returns the value in its stored form: `true` / `false` for bools, the plain number for
+/// integers and comma separated choices for multiselect.
+fn check_var_value(
+    var: &TemplateVar,
+    pattern: Option<&Regex>,
+    value: &str,
+) -> anyhow::Result<String> {
+    let expected_choice = || format!("expected one of: {}", var.choices.join(", "));
+
+    let value = match var.var_type {
+        VarType::String => value.to_string(),
+        VarType::Bool => {
+            parse_bool(value).ok_or_else(|| anyhow::anyhow!("expected true or false"))?.to_string()
+        }
+        VarType::Integer => value
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| anyhow::anyhow!("expected a whole number"))?
+            .to_string(),
+        VarType::Select => {
+            let value = value.trim();
+            if !var.choices.iter().any(|choice| choice == value) {
+                anyhow::bail!(expected_choice());
+            }
+            value.to_string()
+        }
+        VarType::Multiselect => {
+            let selected: Vec<&str> = split_choices(value).collect();
+            if !selected.iter().all(|item| var.choices.iter().any(|choice| choice == item)) {
+                anyhow::bail!(expected_choice());
+            }
+            selected.join(",")
+        }
+    };
+
+    if let Some(pattern) = pattern.filter(|pattern| !pattern.is_match(&value)) {
+        anyhow::bail!(validate_message(var, pattern));
+    }
+    Ok(value)
+}
+
+/// Asks for a variable with the `inquire` prompt matching its type.
+fn prompt_var(var: &TemplateVar, name: &str, pattern: Option<&Regex>) -> anyhow::Result<String> {
+    let message = var.prompt.clone().unwrap_or_else(|| format!("Please input {}:", name));
+    let default = var.default.as_deref();
+
+    let value = match var.var_type {
+        VarType::String => {
+            let mut text = Text::new(&message);
+            if let Some(default) = default {
+                text = text.with_default(default);
+            }
+            if let Some(pattern) = pattern {
+                let (pattern, error) = (pattern.clone(), validate_message(var, pattern));
+                text = text.with_validator(move |input: &str| {
+                    Result::Ok(match pattern.is_match(input) {
+                        true => Validation::Valid,
+                        false => Validation::Invalid(error.clone().into()),
+                    })
+                });
+            }
+            text.prompt()?
+        }
+        VarType::Bool => {
+            let mut confirm = Confirm::new(&message);
+            if let Some(default) = default.and_then(parse_bool) {
+                confirm = confirm.with_default(default);
+            }
+            confirm.prompt()?.to_string()
+        }
+        VarType::Select => {
+            let cursor = default
+                .and_then(|default| var.choices.iter().position(|choice| choice == default))
+                .unwrap_or(0);
+            Select::new(&message, var.choices.clone()).with_starting_cursor(cursor).prompt()?
+        }
+        VarType::Multiselect => {
+            let selected: Vec<usize> = split_choices(default.unwrap_or(""))
+                .filter_map(|item| var.choices.iter().position(|choice| choice == item))
+                .collect();
+            MultiSelect::new(&message, var.choices.clone())
+                .with_default(&selected)
+                .prompt()?
+                .join(",")
+        }
+        VarType::Integer => {
+            let mut input =
+                CustomType::<i64>::new(&message).with_error_message("Please enter a whole number");
+            if let Some(default) = default.and_then(|default| default.trim().parse().ok()) {
+                input = input.with_default(default);
+            }
+            input.prompt()?.to_string()
         }
-        input
     };
 
-    let template = available_templates
-        .get(&selected)
-        .with_context(|| format!("Template '{}' not found in config metadata", selected))?;
+    Ok(value)
+}
+
+/// The answers collected so far as a template engine context.
+fn answers_context(values: &TemplateVarValues) -> template_engine::Context {
+    values.iter().map(|(name, value)| (name.clone(), Value::Str(value.clone()))).collect()
+}
+
+/// Returns the paths of the template's `conditional_paths` whose `when` condition is false
+/// for the collected answers, to be excluded from generation.
+pub fn conditional_excludes(
+    item: &TemplateItem,
+    values: &TemplateVarValues,
+) -> anyhow::Result<Vec<String>> {
+    let context = answers_context(values);
 
-    Ok(template.clone())
+    let mut excludes = vec![];
+    for rule in &item.conditional_paths {
+        let holds = template_engine::evaluate(&rule.when, &context).map_err(|err| {
+            anyhow::anyhow!(
+                "❌ Invalid condition '{}' for {:?}: {}",
+                rule.when,
+                rule.paths,
+                err.message
+            )
+        })?;
+        if !holds.is_truthy() {
+            excludes.extend(rule.paths.iter().cloned());
+        }
+    }
+    Ok(excludes)
 }
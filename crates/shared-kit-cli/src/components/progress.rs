@@ -1,22 +1,31 @@
 use std::{
     fs,
-    io::{Read, Write},
-    path::{Path, PathBuf},
+    io::{self, Read, Write},
+    path::Path,
     sync::Arc,
+    time::Duration,
 };
 
-use crate::helper::file_system::{FileTransformKind, copy_directory_with_replace};
+use crate::helper::file_system::{
+    CopyOptions, FileTransformKind, NameRenderer, copy_directory_with_options,
+};
 use anyhow::Context;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
 use reqwest::blocking::Response;
 
 use crate::helper::{
     file_system::pre_count_files,
-    file_transform_pipe::{FileTransformPipe, copy_file_progress_middleware},
+    file_transform_pipe::{
+        FileTransformPipe, Middleware, copy_file_progress_middleware, filter_files_middleware,
+    },
+    path_matcher::{PathFilter, PathMatcher},
 };
 
-pub fn create_file_progress(path: &PathBuf) -> anyhow::Result<ProgressBar> {
-    let total_files = pre_count_files(path)?;
+pub fn create_file_progress(
+    path: &Path,
+    filter: Option<&PathFilter>,
+) -> anyhow::Result<ProgressBar> {
+    let total_files = pre_count_files(path, filter)?;
     let pb = ProgressBar::new(total_files as u64);
     pb.set_style(
         ProgressStyle::with_template(
@@ -28,54 +37,106 @@ pub fn create_file_progress(path: &PathBuf) -> anyhow::Result<ProgressBar> {
     Ok(pb)
 }
 
-pub fn create_download_progress(resp: &Response) -> anyhow::Result<ProgressBar> {
-    let total_size =
-        resp.content_length().with_context(|| "Failed to get content length from response")?;
-    let pb = ProgressBar::new(total_size);
-    pb.set_style(
-        ProgressStyle::with_template(
-            "[{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})",
-        )
-        .unwrap()
-        .progress_chars("##-"),
-    );
-
-    Ok(pb)
+/// A byte progress bar, or a spinner when the server did not send a `Content-Length`.
+pub fn create_download_progress(total_size: Option<u64>) -> ProgressBar {
+    match total_size {
+        Some(total) => {
+            let pb = ProgressBar::new(total);
+            pb.set_style(
+                ProgressStyle::with_template(
+                    "[{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})",
+                )
+                .unwrap()
+                .progress_chars("##-"),
+            );
+            pb
+        }
+        None => {
+            let pb = ProgressBar::new_spinner();
+            pb.set_style(
+                ProgressStyle::with_template(
+                    "{spinner:.cyan} [{elapsed_precise}] {bytes} ({bytes_per_sec})",
+                )
+                .unwrap(),
+            );
+            pb.enable_steady_tick(Duration::from_millis(100));
+            pb
+        }
+    }
 }
 
+/// Streams a response body into `dest_path`.
+///
+/// Read errors and bodies shorter than the announced `Content-Length` fail the download.
 pub fn download_file_with_progress(resp: Response, dest_path: &Path) -> anyhow::Result<()> {
     let mut dest_file =
         fs::File::create(dest_path).with_context(|| "Failed to create temp zip file")?;
 
-    let pb = create_download_progress(&resp)?;
+    let expected = resp.content_length();
+    let pb = create_download_progress(expected);
 
     let mut downloaded: u64 = 0;
     let mut buffer = [0; 8192];
 
     let mut stream = resp;
-    while let Ok(n) = stream.read(&mut buffer) {
-        if n == 0 {
-            break;
-        }
+    loop {
+        let n = match stream.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                pb.abandon();
+                return Err(err).with_context(|| {
+                    format!("❌ Download interrupted after {}", HumanBytes(downloaded))
+                });
+            }
+        };
         dest_file.write_all(&buffer[..n])?;
         downloaded += n as u64;
         pb.set_position(downloaded);
     }
 
+    if let Some(expected) = expected.filter(|expected| downloaded < *expected) {
+        pb.abandon();
+        anyhow::bail!("❌ Download truncated: received {} of {} bytes", downloaded, expected);
+    }
+
     pb.finish_with_message("Download complete");
 
     Ok(())
 }
 
-pub fn copy_directory_with_progress(origin: &PathBuf, target: &PathBuf) -> anyhow::Result<()> {
-    let pb = create_file_progress(origin)?;
+pub fn copy_directory_with_progress(
+    origin: &Path,
+    target: &Path,
+    filter: Option<PathFilter>,
+    binary: Option<PathMatcher>,
+    middlewares: Vec<Middleware>,
+    render_name: Option<&NameRenderer>,
+) -> anyhow::Result<()> {
+    let pb = create_file_progress(origin, filter.as_ref())?;
     let pb = Arc::new(pb);
 
-    let handle = FileTransformPipe::new()
-        .add(copy_file_progress_middleware(pb.clone(), origin.clone()))
-        .into_handler(|_| FileTransformKind::NoChange);
-
-    copy_directory_with_replace(origin, target, Some(&handle))?;
+    let mut pipe = FileTransformPipe::new();
+    if let Some(filter) = &filter {
+        pipe = pipe.add(filter_files_middleware(Arc::new(filter.clone()), origin.to_path_buf()));
+    }
+    pipe = pipe.add(copy_file_progress_middleware(pb.clone(), origin.to_path_buf()));
+    let pipe = middlewares.into_iter().fold(pipe, |pipe, middleware| pipe.add(middleware));
+    let handle = pipe.into_handler(|_| FileTransformKind::NoChange);
+
+    let force_binary = |path: &Path| {
+        let relative = path.strip_prefix(origin).unwrap_or(path);
+        binary.as_ref().is_some_and(|matcher| matcher.is_match(relative))
+    };
+    let options = CopyOptions {
+        callback: Some(&handle),
+        force_binary: Some(&force_binary),
+        filter: filter.as_ref(),
+        render_name,
+    };
+
+    copy_directory_with_options(origin, target, &options)?;
 
     let total_files = pb.length().unwrap_or(0);
 
@@ -1,21 +1,48 @@
 use std::{
     collections::HashMap,
+    env,
     fs::{self},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{Context, Ok, Result};
 use console::style;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 use crate::{
-    constant::{DEFAULT_CONFIG_DIR, DEFAULT_CONFIG_FILENAME, TemplateKind, Templates},
-    helper::path::expand_dir,
+    constant::{
+        CacheSettings, Credential, DEFAULT_CONFIG_DIR, DEFAULT_CONFIG_FILENAME, ExtractLimits,
+        LOCAL_CONFIG_FILENAMES, NetworkSettings, TemplateItem, TemplateKind, TemplateVarValues,
+        Templates,
+    },
+    helper::{
+        config_edit::{update_json_template, update_yaml_template},
+        file_system::write_file,
+        path::expand_dir,
+        repo::FetchOptions,
+    },
 };
+use toml_edit::DocumentMut;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct ConfigMetadata {
+    #[serde(default)]
     pub templates: Templates,
+    /// Archive extraction limits, the last config layer defining them wins
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract: Option<ExtractLimits>,
+    /// Download cache settings, the last config layer defining them wins
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheSettings>,
+    /// Timeouts, retries and proxy of downloads, the last config layer defining them wins
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkSettings>,
+    /// Self-hosted servers, host (with port) → platform name, merged by host
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub hosts: HashMap<String, String>,
+    /// Private repository tokens, host (with port) → credential, merged by host
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub credentials: HashMap<String, Credential>,
 }
 
 impl ConfigMetadata {
@@ -30,57 +57,168 @@ impl ConfigMetadata {
             None => self.templates.clone(),
         }
     }
-}
 
-impl Default for ConfigMetadata {
-    fn default() -> Self {
-        ConfigMetadata { templates: HashMap::new() }
+    pub fn extract_limits(&self) -> ExtractLimits {
+        self.extract.clone().unwrap_or_default()
+    }
+
+    pub fn cache_settings(&self) -> CacheSettings {
+        self.cache.clone().unwrap_or_default()
+    }
+
+    pub fn network_settings(&self) -> NetworkSettings {
+        self.network.clone().unwrap_or_default()
+    }
+
+    /// Settings for fetching remote templates.
+    pub fn fetch_options(&self, offline: bool) -> FetchOptions {
+        FetchOptions {
+            limits: self.extract_limits(),
+            cache: self.cache_settings(),
+            network: self.network_settings(),
+            offline,
+            hosts: self.hosts.clone(),
+            credentials: self.credentials.clone(),
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct Config {
+    /// The most specific config file: `--config`, then the project-local file, then the global one
     pub current_config_path: Option<PathBuf>,
     pub metadata: ConfigMetadata,
+    /// Config file each template was loaded from, later layers override earlier ones
+    pub template_sources: HashMap<String, PathBuf>,
 }
 
 impl Config {
     pub fn from_path(path: Option<String>) -> Result<Self> {
-        let (config_path, metadata) = Config::parse_config(path)?;
-        Ok(Config { current_config_path: config_path, metadata: metadata })
+        let mut config = Config::default();
+        config.reload(path)?;
+        Ok(config)
     }
 
-    pub fn reload(self: &mut Self, path: Option<String>) -> Result<()> {
-        let (config_path, metadata) = Config::parse_config(path)?;
-        self.current_config_path = config_path;
-        self.metadata = metadata;
+    /// Loads the global config, the nearest project-local `shared-kit.*` and the given
+    /// `path`, merging templates by name with later layers winning.
+    pub fn reload(&mut self, path: Option<String>) -> Result<()> {
+        let explicit = match path {
+            Some(p) => Some(
+                expand_dir(&p).with_context(|| format!("Failed to expand config path: {}", p))?,
+            ),
+            None => None,
+        };
+
+        *self = Config::load_layers(&ConfigRoots::from_env()?, explicit)?;
 
         Ok(())
     }
 
-    fn parse_config(path: Option<String>) -> anyhow::Result<(Option<PathBuf>, ConfigMetadata)> {
-        let config_path = path.as_deref().and_then(expand_dir).or_else(get_default_config_path);
+    /// The config file a template was defined in, falling back to `current_config_path`.
+    pub fn template_source(&self, name: &str) -> Option<&PathBuf> {
+        self.template_sources.get(name).or(self.current_config_path.as_ref())
+    }
 
-        let config_path = match config_path {
-            Some(p) => p,
-            None => {
-                info_msg!("No config path provided; using default configuration.");
-                return Ok((config_path, ConfigMetadata::default()));
-            }
+    fn load_layers(roots: &ConfigRoots, explicit: Option<PathBuf>) -> Result<Self> {
+        let global = roots.global.clone();
+        let local = find_local_config(&roots.cwd, roots.ceiling.as_deref());
+
+        let mut config = Config {
+            current_config_path: explicit.clone().or(local.clone()).or(global.clone()),
+            metadata: ConfigMetadata::default(),
+            template_sources: HashMap::new(),
         };
 
-        if !config_path.exists() {
-            warn_msg!(
-                "Config file not found at: {:?}",
-                style(&config_path.display().to_string()).yellow()
-            );
-            return Ok((Some(config_path), ConfigMetadata::default()));
+        let mut loaded: Vec<PathBuf> = vec![];
+        for (layer, required) in [(global, false), (local, false), (explicit, true)] {
+            let Some(layer) = layer else {
+                continue;
+            };
+
+            if loaded.iter().any(|p| same_file(p, &layer)) {
+                continue;
+            }
+
+            if !layer.exists() {
+                if required {
+                    warn_msg!(
+                        "Config file not found at: {:?}",
+                        style(&layer.display().to_string()).yellow()
+                    );
+                }
+                continue;
+            }
+
+            let metadata = parse_config(&layer)
+                .with_context(|| format!("Failed to load config from {}", layer.display()))?;
+            config.merge(metadata, &layer);
+            loaded.push(layer);
         }
 
-        let metadata = parse_config(&config_path)
-            .with_context(|| format!("Failed to load config from {}", config_path.display()))?;
+        Ok(config)
+    }
+
+    /// Adds or replaces a template in the active config file, creating the file if needed.
+    pub fn save_template(&mut self, name: &str, item: TemplateItem) -> Result<PathBuf> {
+        let path = self
+            .current_config_path
+            .clone()
+            .context("No active config file; pass --config to choose one")?;
 
-        Ok((Some(config_path), metadata))
+        update_config_file(&path, name, Some(&item))?;
+        self.template_sources.insert(name.to_string(), path.clone());
+        self.metadata.templates.insert(name.to_string(), item);
+
+        Ok(path)
+    }
+
+    /// Replaces a template in the config file that defines it.
+    pub fn update_template(&mut self, name: &str, item: TemplateItem) -> Result<PathBuf> {
+        let path = self
+            .template_source(name)
+            .cloned()
+            .with_context(|| format!("Unknown config file for template '{}'", name))?;
+
+        update_config_file(&path, name, Some(&item))?;
+        self.metadata.templates.insert(name.to_string(), item);
+
+        Ok(path)
+    }
+
+    /// Removes a template from the config file that defines it.
+    pub fn remove_template(&mut self, name: &str) -> Result<PathBuf> {
+        if !self.metadata.templates.contains_key(name) {
+            anyhow::bail!("Template '{}' not found in config", name);
+        }
+
+        let path = self
+            .template_source(name)
+            .cloned()
+            .with_context(|| format!("Unknown config file for template '{}'", name))?;
+
+        update_config_file(&path, name, None)?;
+        self.template_sources.remove(name);
+        self.metadata.templates.remove(name);
+
+        Ok(path)
+    }
+
+    fn merge(&mut self, metadata: ConfigMetadata, source: &Path) {
+        if metadata.extract.is_some() {
+            self.metadata.extract = metadata.extract;
+        }
+        if metadata.cache.is_some() {
+            self.metadata.cache = metadata.cache;
+        }
+        if metadata.network.is_some() {
+            self.metadata.network = metadata.network;
+        }
+        self.metadata.hosts.extend(metadata.hosts);
+        self.metadata.credentials.extend(metadata.credentials);
+        for (name, template) in metadata.templates {
+            self.template_sources.insert(name.clone(), source.to_path_buf());
+            self.metadata.templates.insert(name, template);
+        }
     }
 }
 
@@ -89,23 +227,581 @@ impl Default for Config {
         Config {
             current_config_path: get_default_config_path(),
             metadata: ConfigMetadata::default(),
+            template_sources: HashMap::new(),
         }
     }
 }
 
+/// Where the config layers are looked up.
+struct ConfigRoots {
+    /// The global config file
+    global: Option<PathBuf>,
+    /// Directory the search for a project-local config starts from
+    cwd: PathBuf,
+    /// Last directory searched for a project-local config, `None` searches up to the root
+    ceiling: Option<PathBuf>,
+}
+
+impl ConfigRoots {
+    fn from_env() -> Result<Self> {
+        Ok(ConfigRoots {
+            global: get_default_config_path(),
+            cwd: env::current_dir().context("Failed to get current directory")?,
+            ceiling: None,
+        })
+    }
+}
+
+/// Walks up from `start` and returns the first project-local `shared-kit.*` config file,
+/// not looking above `ceiling` when given.
+pub fn find_local_config(start: &Path, ceiling: Option<&Path>) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .take_while(|dir| ceiling.is_none_or(|ceiling| dir.starts_with(ceiling)))
+        .find_map(|dir| {
+            LOCAL_CONFIG_FILENAMES.iter().map(|name| dir.join(name)).find(|path| path.is_file())
+        })
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize().ok(), b.canonicalize().ok()) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
 pub fn get_default_config_path() -> Option<PathBuf> {
     dirs::config_dir().map(|dir| dir.join(DEFAULT_CONFIG_DIR).join(DEFAULT_CONFIG_FILENAME))
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Picks the format from the file extension, files without extension are read as TOML.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+
+        match extension.as_deref() {
+            None | Some("toml") => Ok(ConfigFormat::Toml),
+            Some("json") => Ok(ConfigFormat::Json),
+            Some("yaml") | Some("yml") => Ok(ConfigFormat::Yaml),
+            Some(ext) => anyhow::bail!(
+                "Unsupported config file extension '.{}' for {:?}, expected .toml, .json, .yaml or .yml",
+                ext,
+                path
+            ),
+        }
+    }
+
+    pub fn parse(self, content: &str) -> Result<ConfigMetadata> {
+        self.deserialize(content)
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(self, content: &str) -> Result<T> {
+        let value = match self {
+            ConfigFormat::Toml => toml::from_str(content)?,
+            ConfigFormat::Json => serde_json::from_str(content)?,
+            ConfigFormat::Yaml => serde_yaml_ng::from_str(content)?,
+        };
+
+        Ok(value)
+    }
+}
+
+impl ConfigFormat {
+    /// Inserts (`Some`) or removes (`None`) a template in the raw config content, keeping the
+    /// rest of the document as it is, comments and layout included.
+    ///
+    /// JSON and YAML are edited as text, an edit that would change anything but the template
+    /// is refused instead of rewriting the file.
+    pub fn update_template(
+        self,
+        content: &str,
+        name: &str,
+        item: Option<&TemplateItem>,
+    ) -> Result<String> {
+        match self {
+            ConfigFormat::Toml => {
+                let mut doc = content.parse::<DocumentMut>()?;
+                // Comments of a file without any table are parsed as trailing decor, keep
+                // them above the first table instead of moving them to the end.
+                let mut leading = String::new();
+                if doc.as_table().is_empty() && item.is_some() {
+                    leading = doc.trailing().as_str().unwrap_or_default().to_string();
+                    doc.set_trailing("");
+                }
+
+                if !doc.contains_key("templates") {
+                    let mut templates = toml_edit::Table::new();
+                    templates.set_implicit(true);
+                    doc.insert("templates", toml_edit::Item::Table(templates));
+                }
+                let templates = doc["templates"]
+                    .as_table_mut()
+                    .context("`templates` must be a table in the config file")?;
+
+                match item {
+                    Some(item) => {
+                        let item_doc = toml::to_string(item)?.parse::<DocumentMut>()?;
+                        let mut table = item_doc.as_table().clone();
+                        table.set_implicit(false);
+                        if !leading.is_empty() {
+                            table.decor_mut().set_prefix(leading);
+                        }
+                        templates.insert(name, toml_edit::Item::Table(table));
+                    }
+                    None => {
+                        templates.remove(name);
+                    }
+                }
+
+                Ok(doc.to_string())
+            }
+            ConfigFormat::Json => {
+                let item = item.map(serde_json::to_value).transpose()?;
+                let updated = update_json_template(content, name, item.as_ref())?;
+                self.check_update(content, &updated, name, item)?;
+                Ok(updated)
+            }
+            ConfigFormat::Yaml => {
+                let yaml_item = item.map(serde_yaml_ng::to_value).transpose()?;
+                let updated = update_yaml_template(content, name, yaml_item.as_ref())?;
+                self.check_update(
+                    content,
+                    &updated,
+                    name,
+                    item.map(serde_json::to_value).transpose()?,
+                )?;
+                Ok(updated)
+            }
+        }
+    }
+
+    /// Makes sure a text edit changed nothing but the template, the file is not written
+    /// otherwise.
+    fn check_update(
+        self,
+        before: &str,
+        after: &str,
+        name: &str,
+        item: Option<serde_json::Value>,
+    ) -> Result<()> {
+        let parse = |content: &str| -> Result<serde_json::Value> {
+            if content.trim().is_empty() {
+                return Ok(serde_json::json!({}));
+            }
+            self.deserialize(content)
+        };
+
+        let mut expected = parse(before)?;
+        let root = expected.as_object_mut().context("The config root must be a mapping")?;
+        let templates = root.entry("templates").or_insert_with(|| serde_json::json!({}));
+        let templates = templates
+            .as_object_mut()
+            .context("`templates` must be a mapping in the config file")?;
+        match item {
+            Some(item) => {
+                templates.insert(name.to_string(), item);
+            }
+            None => {
+                templates.shift_remove(name);
+            }
+        }
+
+        if !parse(after).is_ok_and(|actual| actual == expected) {
+            anyhow::bail!(
+                "❌ The {} config cannot be updated without reformatting it, please add or remove the template by hand",
+                self
+            );
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ConfigFormat::Toml => "TOML",
+            ConfigFormat::Json => "JSON",
+            ConfigFormat::Yaml => "YAML",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+fn update_config_file(path: &Path, name: &str, item: Option<&TemplateItem>) -> Result<()> {
+    let format = ConfigFormat::from_path(path)?;
+    let content = if path.exists() {
+        fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file at {:?}", path))?
+    } else {
+        String::new()
+    };
+
+    let updated = format
+        .update_template(&content, name, item)
+        .with_context(|| format!("Failed to update config {} at {:?}", format, path))?;
+
+    write_file(path, &updated)
+}
+
+/// Loads template variable answers from a TOML, JSON or YAML file of `name = value` pairs.
+///
+/// Scalars are converted to strings, lists are joined with `,`.
+pub fn load_answers(path: &Path) -> Result<TemplateVarValues> {
+    let format = ConfigFormat::from_path(path)?;
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read answers file at {:?}", path))?;
+    let answers: HashMap<String, serde_json::Value> = format
+        .deserialize(&content)
+        .with_context(|| format!("Failed to parse answers {} from {:?}", format, path))?;
+
+    answers
+        .into_iter()
+        .map(|(name, value)| {
+            let value = answer_to_string(&value).with_context(|| {
+                format!("Unsupported value for answer '{}' in {:?}", name, path)
+            })?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn answer_to_string(value: &serde_json::Value) -> Result<String> {
+    match value {
+        serde_json::Value::String(s) => Ok(s.clone()),
+        serde_json::Value::Bool(b) => Ok(b.to_string()),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        serde_json::Value::Array(items) => {
+            let items = items.iter().map(answer_to_string).collect::<Result<Vec<_>>>()?;
+            Ok(items.join(","))
+        }
+        other => anyhow::bail!("expected a string, bool, number or list, got {}", other),
+    }
+}
+
 fn parse_config(path: &PathBuf) -> Result<ConfigMetadata> {
     if !&path.is_file() {
         anyhow::bail!("The config path is not a valid file: {:?}", path);
     }
 
-    let content = fs::read_to_string(&path)
+    let format = ConfigFormat::from_path(path)?;
+    let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read config file at {:?}", path))?;
-    let config: ConfigMetadata = toml::from_str(&content)
-        .with_context(|| format!("Failed to parse config TOML from {:?}", path))?;
+    let config = format
+        .parse(&content)
+        .with_context(|| format!("Failed to parse config {} from {:?}", format, path))?;
 
     Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant::TemplateKind;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_config_toml() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("metadata.toml");
+        fs::write(&path, "[templates.basic]\nkind = \"Package\"\ntemplate = \"./basic\"\n")
+            .unwrap();
+
+        let metadata = parse_config(&path).unwrap();
+        assert_eq!(metadata.templates["basic"].kind, TemplateKind::Package);
+        assert_eq!(metadata.templates["basic"].template.as_deref(), Some("./basic"));
+    }
+
+    #[test]
+    fn test_parse_config_json() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shared-kit.json");
+        fs::write(
+            &path,
+            r#"{ "templates": { "basic": { "kind": "Project", "repo": "octocat/Hello-World" } } }"#,
+        )
+        .unwrap();
+
+        let metadata = parse_config(&path).unwrap();
+        assert_eq!(metadata.templates["basic"].kind, TemplateKind::Project);
+        assert_eq!(metadata.templates["basic"].repo.as_deref(), Some("octocat/Hello-World"));
+    }
+
+    #[test]
+    fn test_parse_config_yaml() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shared-kit.yml");
+        fs::write(&path, "templates:\n  basic:\n    kind: Monorepo\n    template: ./basic\n")
+            .unwrap();
+
+        let metadata = parse_config(&path).unwrap();
+        assert_eq!(metadata.templates["basic"].kind, TemplateKind::Monorepo);
+    }
+
+    /// Roots that never leave `dir`, so the user's own config files stay out of the tests.
+    fn roots(dir: &Path, global: Option<PathBuf>, cwd: &Path) -> ConfigRoots {
+        ConfigRoots { global, cwd: cwd.to_path_buf(), ceiling: Some(dir.to_path_buf()) }
+    }
+
+    fn write_template(path: &Path, name: &str, template: &str) {
+        fs::write(
+            path,
+            format!("[templates.{}]\nkind = \"Package\"\ntemplate = \"{}\"\n", name, template),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_load_layers_merges_by_name() {
+        let dir = tempdir().unwrap();
+        let global = dir.path().join("global.toml");
+        let project = dir.path().join("project");
+        let nested = project.join("packages/app");
+        fs::create_dir_all(&nested).unwrap();
+        let local = project.join("shared-kit.toml");
+        let explicit = dir.path().join("explicit.json");
+
+        fs::write(
+            &global,
+            "[templates.shared]\nkind = \"Package\"\ntemplate = \"./global-shared\"\n\n\
+             [templates.global-only]\nkind = \"Package\"\ntemplate = \"./global-only\"\n",
+        )
+        .unwrap();
+        write_template(&local, "shared", "./local-shared");
+        fs::write(
+            &explicit,
+            r#"{ "templates": { "explicit-only": { "kind": "Project", "template": "./e" } } }"#,
+        )
+        .unwrap();
+
+        let config = Config::load_layers(
+            &roots(dir.path(), Some(global.clone()), &nested),
+            Some(explicit.clone()),
+        )
+        .unwrap();
+
+        assert_eq!(config.current_config_path.as_ref(), Some(&explicit));
+        assert_eq!(config.metadata.templates.len(), 3);
+        assert_eq!(config.metadata.templates["shared"].template.as_deref(), Some("./local-shared"));
+        assert_eq!(config.template_source("shared"), Some(&local));
+        assert_eq!(config.template_source("global-only"), Some(&global));
+        assert_eq!(config.template_source("explicit-only"), Some(&explicit));
+    }
+
+    #[test]
+    fn test_load_layers_without_explicit_uses_local_as_current() {
+        let dir = tempdir().unwrap();
+        let local = dir.path().join("shared-kit.yaml");
+        fs::write(&local, "templates:\n  basic:\n    kind: Package\n    template: ./basic\n")
+            .unwrap();
+
+        let config = Config::load_layers(
+            &roots(dir.path(), Some(dir.path().join("missing.toml")), dir.path()),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(config.current_config_path.as_ref(), Some(&local));
+        assert_eq!(config.template_source("basic"), Some(&local));
+    }
+
+    #[test]
+    fn test_load_layers_extract_limits() {
+        let dir = tempdir().unwrap();
+        let global = dir.path().join("global.toml");
+        let local = dir.path().join("shared-kit.toml");
+        fs::write(&global, "[extract]\nmax_files = 10\nmax_total_size = 2048\n").unwrap();
+        fs::write(&local, "[extract]\nmax_files = 20\n").unwrap();
+
+        let config =
+            Config::load_layers(&roots(dir.path(), Some(global), dir.path()), None).unwrap();
+
+        let limits = config.metadata.extract_limits();
+        assert_eq!(limits.max_files, 20);
+        assert_eq!(limits.max_total_size, ExtractLimits::default().max_total_size);
+    }
+
+    #[test]
+    fn test_load_layers_merges_hosts() {
+        let dir = tempdir().unwrap();
+        let global = dir.path().join("global.toml");
+        let local = dir.path().join("shared-kit.yaml");
+        fs::write(&global, "[hosts]\n\"git.a.com\" = \"gitea\"\n\"git.b.com\" = \"gitlab\"\n")
+            .unwrap();
+        fs::write(&local, "hosts:\n  git.a.com: forgejo\n").unwrap();
+
+        let config =
+            Config::load_layers(&roots(dir.path(), Some(global), dir.path()), None).unwrap();
+
+        assert_eq!(config.metadata.hosts["git.a.com"], "forgejo");
+        assert_eq!(config.metadata.hosts["git.b.com"], "gitlab");
+    }
+
+    #[test]
+    fn test_find_local_config_none() {
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(find_local_config(&nested, Some(dir.path())), None);
+    }
+
+    #[test]
+    fn test_find_local_config_stops_at_ceiling() {
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        let local = dir.path().join("shared-kit.toml");
+        write_template(&local, "basic", "./basic");
+
+        assert_eq!(find_local_config(&nested, Some(dir.path())), Some(local));
+        assert_eq!(find_local_config(&nested, Some(&dir.path().join("a"))), None);
+    }
+
+    #[test]
+    fn test_parse_config_without_templates() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("shared-kit.json");
+        fs::write(&path, r#"{ "watch": [{ "path": "./src", "cmd": "echo changed!" }] }"#).unwrap();
+
+        let metadata = parse_config(&path).unwrap();
+        assert!(metadata.templates.is_empty());
+    }
+
+    fn sample_item() -> TemplateItem {
+        TemplateItem {
+            kind: TemplateKind::Project,
+            repo: Some("octocat/Hello-World".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_update_template_toml_keeps_comments() {
+        let content = "# my templates\n[templates.basic]\n# local one\nkind = \"Package\"\ntemplate = \"./basic\"\n";
+
+        let added =
+            ConfigFormat::Toml.update_template(content, "hello", Some(&sample_item())).unwrap();
+        assert!(added.starts_with(content));
+        let metadata = ConfigFormat::Toml.parse(&added).unwrap();
+        assert_eq!(metadata.templates["hello"], sample_item());
+
+        let removed = ConfigFormat::Toml.update_template(&added, "hello", None).unwrap();
+        assert_eq!(removed, content);
+    }
+
+    #[test]
+    fn test_update_template_toml_comment_only_file() {
+        let content = "# shared templates\n";
+
+        let added =
+            ConfigFormat::Toml.update_template(content, "hello", Some(&sample_item())).unwrap();
+        assert!(added.starts_with("# shared templates\n[templates.hello]"), "{}", added);
+    }
+
+    #[test]
+    fn test_update_template_toml_empty_file() {
+        let added = ConfigFormat::Toml.update_template("", "hello", Some(&sample_item())).unwrap();
+
+        assert!(added.starts_with("[templates.hello]"));
+        assert_eq!(ConfigFormat::Toml.parse(&added).unwrap().templates["hello"], sample_item());
+    }
+
+    #[test]
+    fn test_update_template_json_keeps_key_order() {
+        let content = r#"{ "watch": [], "templates": { "b": { "kind": "Package" }, "a": { "kind": "Package" } } }"#;
+
+        let added =
+            ConfigFormat::Json.update_template(content, "hello", Some(&sample_item())).unwrap();
+        let watch = added.find("watch").unwrap();
+        let b = added.find("\"b\"").unwrap();
+        let a = added.find("\"a\"").unwrap();
+        let hello = added.find("hello").unwrap();
+        assert!(watch < b && b < a && a < hello);
+
+        let removed = ConfigFormat::Json.update_template(&added, "b", None).unwrap();
+        let metadata = ConfigFormat::Json.parse(&removed).unwrap();
+        assert!(!metadata.templates.contains_key("b"));
+        assert!(metadata.templates.contains_key("hello"));
+    }
+
+    #[test]
+    fn test_update_template_yaml() {
+        let content = "templates:\n  basic:\n    kind: Package\n    template: ./basic\n";
+
+        let added =
+            ConfigFormat::Yaml.update_template(content, "hello", Some(&sample_item())).unwrap();
+        let metadata = ConfigFormat::Yaml.parse(&added).unwrap();
+        assert_eq!(metadata.templates.len(), 2);
+        assert_eq!(metadata.templates["hello"], sample_item());
+        assert!(!added.contains("null"), "{}", added);
+    }
+
+    #[test]
+    fn test_save_and_remove_template() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested/metadata.toml");
+        let mut config = Config { current_config_path: Some(path.clone()), ..Default::default() };
+
+        config.save_template("hello", sample_item()).unwrap();
+        assert_eq!(parse_config(&path).unwrap().templates["hello"], sample_item());
+        assert_eq!(config.template_source("hello"), Some(&path));
+
+        config.remove_template("hello").unwrap();
+        assert!(parse_config(&path).unwrap().templates.is_empty());
+        assert!(config.remove_template("hello").is_err());
+    }
+
+    #[test]
+    fn test_load_answers() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("answers.toml");
+        fs::write(
+            &path,
+            "project_name = \"demo\"\nuse_ts = true\nport = 3000\nfeatures = [\"a\", \"b\"]\n",
+        )
+        .unwrap();
+
+        let answers = load_answers(&path).unwrap();
+        assert_eq!(answers["project_name"], "demo");
+        assert_eq!(answers["use_ts"], "true");
+        assert_eq!(answers["port"], "3000");
+        assert_eq!(answers["features"], "a,b");
+    }
+
+    #[test]
+    fn test_load_answers_nested_table_should_fail() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("answers.json");
+        fs::write(&path, r#"{ "nested": { "a": 1 } }"#).unwrap();
+
+        let err = format!("{:#}", load_answers(&path).unwrap_err());
+        assert!(err.contains("Unsupported value for answer 'nested'"));
+    }
+
+    #[test]
+    fn test_parse_config_unsupported_extension() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("metadata.ini");
+        fs::write(&path, "").unwrap();
+
+        let err = parse_config(&path).unwrap_err().to_string();
+        assert!(err.contains("Unsupported config file extension"));
+    }
+
+    #[test]
+    fn test_parse_config_invalid_json_names_format() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("metadata.json");
+        fs::write(&path, "{ not json").unwrap();
+
+        let err = parse_config(&path).unwrap_err().to_string();
+        assert!(err.contains("Failed to parse config JSON"));
+    }
+}
@@ -5,20 +5,244 @@ use serde::{Deserialize, Serialize};
 
 pub const DEFAULT_CONFIG_DIR: &str = "shared-kit-cli";
 pub const DEFAULT_CONFIG_FILENAME: &str = "metadata.toml";
+/// Environment variable overriding the download cache directory
+pub const CACHE_DIR_ENV: &str = "SHARED_KIT_CACHE_DIR";
+/// Project-local config files looked up from the current directory upwards
+pub const LOCAL_CONFIG_FILENAMES: [&str; 4] =
+    ["shared-kit.toml", "shared-kit.json", "shared-kit.yaml", "shared-kit.yml"];
 
-#[derive(Debug, Clone, ValueEnum, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, ValueEnum, PartialEq, Eq, Deserialize, Serialize)]
 pub enum TemplateKind {
     Project,
     Monorepo,
+    #[default]
     Package,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+/// What `new` does when the target directory already exists
+#[derive(Debug, Clone, ValueEnum, PartialEq, Eq)]
+pub enum OnExistsAction {
+    /// Replace the existing directory, it is moved aside and restored when generation fails
+    Overwrite,
+    /// Abort with an error
+    Fail,
+    /// Copy into the existing directory, replacing files with the same name
+    Merge,
+}
+
+/// Kind of value a template variable takes, decides the prompt and how values are checked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VarType {
+    /// Free text
+    #[default]
+    String,
+    /// `true` or `false`, asked as a yes / no question
+    Bool,
+    /// One of `choices`
+    Select,
+    /// Any number of `choices`, stored comma separated
+    Multiselect,
+    /// A whole number
+    Integer,
+}
+
+impl VarType {
+    pub fn is_string(&self) -> bool {
+        *self == VarType::String
+    }
+}
+
+impl std::fmt::Display for VarType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            VarType::String => "string",
+            VarType::Bool => "bool",
+            VarType::Select => "select",
+            VarType::Multiselect => "multiselect",
+            VarType::Integer => "integer",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TemplateVar {
+    /// The placeholder used in the template files, e.g. `{{project_name}}`
+    pub placeholder: String,
+    /// Message shown to the user when prompting for the value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Value used when the user provides no input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Kind of value, `string` when not set
+    #[serde(rename = "type", default, skip_serializing_if = "VarType::is_string")]
+    pub var_type: VarType,
+    /// Options offered by `select` and `multiselect` variables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+    /// Regex the value has to match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validate: Option<String>,
+    /// Error shown when the value does not match `validate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validate_message: Option<String>,
+    /// Only ask for the value when this condition on earlier answers holds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    /// Derive the value instead of asking: a template expression over the answers, or one of
+    /// `env:NAME`, `git:KEY`, `now:FORMAT` and `uuid`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compute: Option<String>,
+    /// Limit replacement to these files, plain paths, globs or `regex:` patterns
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub includes_paths: Vec<String>,
+    /// Skip replacement in these files, plain paths, globs or `regex:` patterns
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excludes_paths: Vec<String>,
+}
+
+impl TemplateVar {
+    /// Variable name derived from the placeholder, `{{project_name}}` -> `project_name`
+    pub fn name(&self) -> &str {
+        let name = self.placeholder.trim();
+        let name = name.strip_prefix("{{").unwrap_or(name);
+        let name = name.strip_suffix("}}").unwrap_or(name);
+        name.trim()
+    }
+}
+
+/// Paths left out of the generated project unless `when` is true for the collected answers
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ConditionalPaths {
+    /// Plain paths, globs or `regex:` patterns, relative to the template directory
+    pub paths: Vec<String>,
+    /// Condition on template variables, e.g. `docker == true`
+    pub when: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct TemplateItem {
     pub kind: TemplateKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub repo: Option<String>,
+    /// Directory inside `repo` used as the template, wins over a subpath in the repo address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo_subdir: Option<String>,
+    /// SHA-256 of the `repo` archive, downloads with a different hash are rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Files or directories to keep, plain relative paths or `regex:` patterns
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub includes: Vec<String>,
+    /// Files or directories to leave out, plain relative paths or `regex:` patterns
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excludes: Vec<String>,
+    /// Files always copied byte-for-byte without variable replacement, on top of the files
+    /// detected as binary from their content
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub binary: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub template_vars: Vec<TemplateVar>,
+    /// Files or directories only generated when their `when` condition holds
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditional_paths: Vec<ConditionalPaths>,
+    /// Commands run after generation, `CD_TARGET` switches into the generated directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub completed_script: Vec<String>,
 }
 
 pub type Templates = HashMap<String, TemplateItem>;
 
+/// Limits applied when extracting downloaded repository archives
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ExtractLimits {
+    /// Maximum total uncompressed size in bytes
+    pub max_total_size: u64,
+    /// Maximum number of entries in the archive
+    pub max_files: usize,
+    /// Maximum uncompressed / compressed ratio of a single entry
+    pub max_compression_ratio: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        ExtractLimits {
+            max_total_size: 1024 * 1024 * 1024,
+            max_files: 50_000,
+            max_compression_ratio: 200,
+        }
+    }
+}
+
+/// Access token for a private repository host
+#[derive(Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Credential {
+    /// User name, only needed for basic auth (Bitbucket app passwords, `.netrc` entries)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    pub token: String,
+}
+
+impl std::fmt::Debug for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credential")
+            .field("username", &self.username)
+            .field("token", &"***")
+            .finish()
+    }
+}
+
+/// Download cache settings for remote templates
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    /// Seconds a cached archive is used before it is revalidated with the server
+    pub ttl: u64,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings { ttl: 24 * 60 * 60 }
+    }
+}
+
+/// HTTP settings for archive downloads and platform API requests
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// Seconds to wait for a connection
+    pub connect_timeout: u64,
+    /// Seconds a request may wait for the server, per read
+    pub timeout: u64,
+    /// Retries of a failed download
+    pub retries: u32,
+    /// Milliseconds before the first retry, doubled for each further retry
+    pub retry_delay: u64,
+    /// Proxy URL for all requests, overrides `HTTPS_PROXY` / `HTTP_PROXY` / `ALL_PROXY`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Comma separated hosts reached without `proxy`, overrides `NO_PROXY`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        NetworkSettings {
+            connect_timeout: 10,
+            timeout: 30,
+            retries: 3,
+            retry_delay: 1000,
+            proxy: None,
+            no_proxy: None,
+        }
+    }
+}
+
+/// Collected template variable values keyed by variable name
+pub type TemplateVarValues = HashMap<String, String>;
@@ -0,0 +1,283 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::constant::{CACHE_DIR_ENV, DEFAULT_CONFIG_DIR};
+
+const ARCHIVE_FILENAME: &str = "archive.zip";
+const PARTIAL_FILENAME: &str = "archive.zip.part";
+const META_FILENAME: &str = "meta.json";
+
+/// Validators and bookkeeping stored next to a cached archive
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CacheMeta {
+    /// The URL the archive was downloaded from
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// Unix timestamp of the last download or successful revalidation
+    pub fetched_at: u64,
+}
+
+impl CacheMeta {
+    /// Seconds since the archive was last downloaded or revalidated.
+    pub fn age(&self) -> u64 {
+        now_secs().saturating_sub(self.fetched_at)
+    }
+
+    /// Whether the archive can be used without asking the server again.
+    pub fn is_fresh(&self, ttl: u64) -> bool {
+        self.age() < ttl
+    }
+}
+
+/// One cached archive, stored in its own directory below the cache root
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    /// `<platform>/<user>/<repo>/<ref>`
+    pub key: String,
+    pub dir: PathBuf,
+}
+
+impl CacheEntry {
+    pub fn archive_path(&self) -> PathBuf {
+        self.dir.join(ARCHIVE_FILENAME)
+    }
+
+    /// Where a download is written before it replaces the cached archive.
+    pub fn partial_path(&self) -> PathBuf {
+        self.dir.join(PARTIAL_FILENAME)
+    }
+
+    /// Reads the metadata of a complete entry, `None` when the entry is missing or broken.
+    pub fn read_meta(&self) -> Option<CacheMeta> {
+        if !self.archive_path().is_file() {
+            return None;
+        }
+        let content = fs::read_to_string(self.dir.join(META_FILENAME)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn write_meta(&self, meta: &CacheMeta) -> anyhow::Result<()> {
+        let path = self.dir.join(META_FILENAME);
+        let content = serde_json::to_string_pretty(meta)?;
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write cache metadata: {}", path.display()))
+    }
+
+    /// Creates the entry directory and returns the path a download should be written to.
+    pub fn prepare_download(&self) -> anyhow::Result<PathBuf> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create cache dir: {}", self.dir.display()))?;
+        Ok(self.partial_path())
+    }
+
+    /// Moves a finished download into place and records its metadata.
+    pub fn commit_download(&self, meta: &CacheMeta) -> anyhow::Result<()> {
+        fs::rename(self.partial_path(), self.archive_path())
+            .with_context(|| format!("Failed to store archive in cache: {}", self.key))?;
+        self.write_meta(meta)
+    }
+
+    /// Size of the cached archive in bytes.
+    pub fn size(&self) -> u64 {
+        fs::metadata(self.archive_path()).map(|m| m.len()).unwrap_or(0)
+    }
+}
+
+/// The download cache for remote template archives.
+pub struct RepoCache {
+    root: PathBuf,
+}
+
+impl RepoCache {
+    pub fn new(root: PathBuf) -> Self {
+        RepoCache { root }
+    }
+
+    /// Opens the cache below the user cache dir, `SHARED_KIT_CACHE_DIR` overrides the location.
+    pub fn open_default() -> anyhow::Result<Self> {
+        if let Some(dir) = std::env::var_os(CACHE_DIR_ENV).filter(|d| !d.is_empty()) {
+            return Ok(RepoCache::new(PathBuf::from(dir)));
+        }
+
+        let dir = dirs::cache_dir().context("Failed to locate the user cache directory")?;
+        Ok(RepoCache::new(dir.join(DEFAULT_CONFIG_DIR).join("repos")))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn entry(&self, key: &str) -> CacheEntry {
+        CacheEntry { key: key.to_string(), dir: self.root.join(key) }
+    }
+
+    /// Lists every complete entry, sorted by key.
+    pub fn entries(&self) -> anyhow::Result<Vec<CacheEntry>> {
+        fn walk(root: &Path, dir: &Path, entries: &mut Vec<CacheEntry>) -> anyhow::Result<()> {
+            if dir.join(META_FILENAME).is_file() {
+                let key = dir
+                    .strip_prefix(root)
+                    .unwrap()
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                entries.push(CacheEntry { key, dir: dir.to_path_buf() });
+                return Ok(());
+            }
+
+            for entry in fs::read_dir(dir)
+                .with_context(|| format!("Failed to read cache dir: {}", dir.display()))?
+            {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    walk(root, &entry.path(), entries)?;
+                }
+            }
+            Ok(())
+        }
+
+        let mut entries = vec![];
+        if self.root.is_dir() {
+            walk(&self.root, &self.root, &mut entries)?;
+        }
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(entries)
+    }
+
+    /// Removes every entry, returns the number of removed entries.
+    ///
+    /// Only entry directories are removed, other files below the cache root are kept.
+    pub fn clean(&self) -> anyhow::Result<usize> {
+        let entries = self.entries()?;
+        for entry in &entries {
+            self.remove_entry(entry)?;
+        }
+        Ok(entries.len())
+    }
+
+    /// Removes entries last fetched more than `max_age` seconds ago, and broken entries.
+    pub fn prune(&self, max_age: u64) -> anyhow::Result<usize> {
+        let mut removed = 0;
+        for entry in self.entries()? {
+            let stale = entry.read_meta().is_none_or(|meta| meta.age() > max_age);
+            if stale {
+                self.remove_entry(&entry)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Removes an entry directory and the parent directories it leaves empty, up to the root.
+    fn remove_entry(&self, entry: &CacheEntry) -> anyhow::Result<()> {
+        fs::remove_dir_all(&entry.dir)
+            .with_context(|| format!("Failed to remove cache entry: {}", entry.dir.display()))?;
+
+        let parents = entry.dir.ancestors().skip(1).take_while(|dir| *dir != self.root);
+        for dir in parents {
+            let empty = fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none());
+            if !empty {
+                break;
+            }
+            fs::remove_dir(dir)
+                .with_context(|| format!("Failed to remove cache dir: {}", dir.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Escapes a cache key component so it is a single, safe path segment.
+pub fn key_component(value: &str) -> String {
+    let escaped: String = value
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c.to_string(),
+            c => c.to_string().bytes().map(|b| format!("%{:02X}", b)).collect(),
+        })
+        .collect();
+
+    match escaped.as_str() {
+        "" | "." | ".." => escaped.replace('.', "%2E") + "_",
+        _ => escaped,
+    }
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn seed(cache: &RepoCache, key: &str, fetched_at: u64) -> CacheEntry {
+        let entry = cache.entry(key);
+        fs::write(entry.prepare_download().unwrap(), b"zip").unwrap();
+        entry
+            .commit_download(&CacheMeta {
+                url: "https://example.com/a.zip".to_string(),
+                fetched_at,
+                ..Default::default()
+            })
+            .unwrap();
+        entry
+    }
+
+    #[test]
+    fn test_key_component_escapes_separators() {
+        assert_eq!(key_component("feature/login"), "feature%2Flogin");
+        assert_eq!(key_component("v1.0.0"), "v1.0.0");
+        assert_eq!(key_component(".."), "%2E%2E_");
+        assert_eq!(key_component(""), "_");
+    }
+
+    #[test]
+    fn test_entries_and_meta_round_trip() {
+        let dir = tempdir().unwrap();
+        let cache = RepoCache::new(dir.path().to_path_buf());
+        seed(&cache, "github/octocat/hello/branch-main", now_secs());
+        seed(&cache, "gitlab/org/app/default", now_secs());
+
+        let entries = cache.entries().unwrap();
+        let keys: Vec<_> = entries.iter().map(|e| e.key.as_str()).collect();
+
+        assert_eq!(keys, ["github/octocat/hello/branch-main", "gitlab/org/app/default"]);
+        assert_eq!(entries[0].size(), 3);
+        assert!(entries[0].read_meta().unwrap().is_fresh(60));
+    }
+
+    #[test]
+    fn test_prune_removes_stale_entries() {
+        let dir = tempdir().unwrap();
+        let cache = RepoCache::new(dir.path().to_path_buf());
+        seed(&cache, "github/a/old/default", now_secs() - 10 * 86400);
+        seed(&cache, "github/a/new/default", now_secs());
+
+        assert_eq!(cache.prune(86400).unwrap(), 1);
+
+        let keys: Vec<_> = cache.entries().unwrap().into_iter().map(|e| e.key).collect();
+        assert_eq!(keys, ["github/a/new/default"]);
+    }
+
+    #[test]
+    fn test_clean_removes_everything() {
+        let dir = tempdir().unwrap();
+        let cache = RepoCache::new(dir.path().join("repos"));
+        seed(&cache, "github/a/b/default", now_secs());
+
+        assert_eq!(cache.clean().unwrap(), 1);
+        assert!(cache.entries().unwrap().is_empty());
+        assert!(!cache.root().join("github").exists());
+    }
+}
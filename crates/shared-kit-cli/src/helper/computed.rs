@@ -0,0 +1,126 @@
+use ring::rand::{SecureRandom, SystemRandom};
+use time::OffsetDateTime;
+
+use crate::helper::{
+    git,
+    template_engine::{self, Context},
+};
+
+/// Format used by `now:` without an explicit format
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Computes the value of a template variable's `compute` source.
+///
+/// - `env:NAME` reads an environment variable
+/// - `git:KEY` reads the git config, e.g. `git:user.name`
+/// - `now:FORMAT` formats the current local time, `%Y-%m-%d` when no format is given
+/// - `uuid` generates a random UUID (v4)
+/// - anything else is a template expression over the answers, e.g. `project_name | snake_case`
+///
+/// Unset environment variables and git config keys compute to an empty value.
+pub fn compute_value(source: &str, context: &Context) -> anyhow::Result<String> {
+    let source = source.trim();
+
+    if let Some(name) = source.strip_prefix("env:") {
+        return Ok(std::env::var(name.trim()).unwrap_or_default());
+    }
+    if let Some(key) = source.strip_prefix("git:") {
+        return Ok(git::config_value(key.trim()).unwrap_or_default());
+    }
+    if let Some(format) = source.strip_prefix("now:") {
+        let format = if format.is_empty() { DEFAULT_DATE_FORMAT } else { format };
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        return format_date(now, format);
+    }
+    if source == "uuid" {
+        return random_uuid();
+    }
+
+    let value = template_engine::evaluate(source, context).map_err(|err| {
+        anyhow::anyhow!("❌ Invalid compute expression '{}': {}", source, err.message)
+    })?;
+    Ok(value.to_string())
+}
+
+/// Formats a date with the `strftime` specifiers `%Y %y %m %d %H %M %S %%`.
+fn format_date(date: OffsetDateTime, format: &str) -> anyhow::Result<String> {
+    let mut output = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => output.push_str(&date.year().to_string()),
+            Some('y') => output.push_str(&format!("{:02}", date.year() % 100)),
+            Some('m') => output.push_str(&format!("{:02}", u8::from(date.month()))),
+            Some('d') => output.push_str(&format!("{:02}", date.day())),
+            Some('H') => output.push_str(&format!("{:02}", date.hour())),
+            Some('M') => output.push_str(&format!("{:02}", date.minute())),
+            Some('S') => output.push_str(&format!("{:02}", date.second())),
+            Some('%') => output.push('%'),
+            other => anyhow::bail!(
+                "❌ Unsupported date format '%{}' in '{}', use %Y %y %m %d %H %M %S",
+                other.map(String::from).unwrap_or_default(),
+                format
+            ),
+        }
+    }
+
+    Ok(output)
+}
+
+fn random_uuid() -> anyhow::Result<String> {
+    let mut bytes = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| anyhow::anyhow!("❌ Failed to generate random bytes for a UUID"))?;
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::template_engine::Value;
+    use time::{Date, Month, Time};
+
+    #[test]
+    fn test_compute_expression_and_env() {
+        let context = Context::from([("project_name".to_string(), Value::Str("My App".into()))]);
+
+        assert_eq!(compute_value("project_name | snake_case", &context).unwrap(), "my_app");
+        assert_eq!(compute_value("env:SHARED_KIT_SURELY_UNSET_VAR", &context).unwrap(), "");
+        assert_eq!(compute_value("env:PATH", &context).unwrap(), std::env::var("PATH").unwrap());
+
+        let err = compute_value("missing | upper", &context).unwrap_err();
+        assert!(err.to_string().contains("unknown variable 'missing'"), "{}", err);
+    }
+
+    #[test]
+    fn test_format_date() {
+        let date = Date::from_calendar_date(2026, Month::March, 7).unwrap();
+        let date = date.with_time(Time::from_hms(9, 5, 2).unwrap()).assume_utc();
+
+        assert_eq!(format_date(date, "%Y").unwrap(), "2026");
+        assert_eq!(format_date(date, DEFAULT_DATE_FORMAT).unwrap(), "2026-03-07");
+        assert_eq!(format_date(date, "%y%m%d-%H:%M:%S 100%%").unwrap(), "260307-09:05:02 100%");
+        assert!(format_date(date, "%Q").is_err());
+        assert_eq!(compute_value("now:%Y", &Context::new()).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_random_uuid() {
+        let uuid = compute_value("uuid", &Context::new()).unwrap();
+
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert!("89ab".contains(&uuid[19..20]));
+        assert_ne!(uuid, random_uuid().unwrap());
+    }
+}
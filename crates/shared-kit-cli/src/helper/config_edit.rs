@@ -0,0 +1,464 @@
+//! Text edits of JSON and YAML config files that only touch the edited template, so
+//! formatting, comments and key order of the rest of the file stay as they are.
+
+use anyhow::Context;
+use serde::Serialize;
+
+/// Byte positions of an object member, `start` is the opening quote of its key.
+struct Member {
+    key: String,
+    start: usize,
+    separator: String,
+    value_start: usize,
+    value_end: usize,
+}
+
+/// Byte positions of a JSON object and its members.
+struct JsonObject {
+    open: usize,
+    close: usize,
+    members: Vec<Member>,
+}
+
+/// Inserts, replaces (`Some`) or removes (`None`) `templates.<name>` in a JSON document.
+pub fn update_json_template(
+    content: &str,
+    name: &str,
+    item: Option<&serde_json::Value>,
+) -> anyhow::Result<String> {
+    let Some(open) = content.find(|c: char| !c.is_whitespace()) else {
+        let Some(item) = item else {
+            return Ok(content.to_string());
+        };
+        let document = serde_json::json!({ "templates": { name: item } });
+        return Ok(format!("{}\n", serde_json::to_string_pretty(&document)?));
+    };
+
+    let root = scan_object(content, open)?;
+    let unit = json_indent_unit(content, &root);
+    let Some(templates) = root.members.iter().find(|member| member.key == "templates") else {
+        let Some(item) = item else {
+            return Ok(content.to_string());
+        };
+        let value = serde_json::json!({ name: item });
+        return insert_json_member(content, &root, "templates", &value, &unit);
+    };
+
+    let templates = scan_object(content, templates.value_start)
+        .context("`templates` must be an object in the config file")?;
+    let existing = templates.members.iter().position(|member| member.key == name);
+
+    Ok(match (item, existing) {
+        (Some(item), Some(index)) => {
+            let member = &templates.members[index];
+            let value = render_json_beside(content, member, item, &unit)?;
+            splice(content, member.value_start..member.value_end, &value)
+        }
+        (Some(item), None) => insert_json_member(content, &templates, name, item, &unit)?,
+        (None, Some(index)) => remove_json_member(content, &templates, index),
+        (None, None) => content.to_string(),
+    })
+}
+
+fn insert_json_member(
+    content: &str,
+    object: &JsonObject,
+    key: &str,
+    value: &serde_json::Value,
+    unit: &str,
+) -> anyhow::Result<String> {
+    let key = serde_json::Value::from(key).to_string();
+    let multiline = content.contains('\n');
+
+    match object.members.last() {
+        Some(last) => {
+            let first = &object.members[0];
+            let value = render_json_beside(content, first, value, unit)?;
+            let gap = if !starts_line(content, first.start) {
+                if first.separator.ends_with(' ') { ", ".to_string() } else { ",".to_string() }
+            } else {
+                format!(",\n{}", line_indent(content, first.start))
+            };
+            let member = format!("{}{}{}{}", gap, key, first.separator, value);
+            Ok(splice(content, last.value_end..last.value_end, &member))
+        }
+        None if multiline => {
+            let parent = line_indent(content, object.open);
+            let indent = format!("{}{}", parent, unit);
+            let value = render_json(value, unit, &indent)?;
+            let body = format!("\n{}{}: {}\n{}", indent, key, value, parent);
+            Ok(splice(content, object.open + 1..object.close, &body))
+        }
+        None => {
+            let body = format!("{}:{}", key, value);
+            Ok(splice(content, object.open + 1..object.close, &body))
+        }
+    }
+}
+
+fn remove_json_member(content: &str, object: &JsonObject, index: usize) -> String {
+    let members = &object.members;
+    let range = if members.len() == 1 {
+        object.open + 1..object.close
+    } else if index + 1 < members.len() {
+        members[index].start..members[index + 1].start
+    } else {
+        members[index - 1].value_end..members[index].value_end
+    };
+    splice(content, range, "")
+}
+
+/// Renders a value in the style of `member`: pretty printed when the member starts its own
+/// line, compact otherwise.
+fn render_json_beside(
+    content: &str,
+    member: &Member,
+    value: &serde_json::Value,
+    unit: &str,
+) -> anyhow::Result<String> {
+    if starts_line(content, member.start) {
+        render_json(value, unit, line_indent(content, member.start))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// Pretty prints a value with the file's indentation, continuing lines at `indent`.
+fn render_json(value: &serde_json::Value, unit: &str, indent: &str) -> anyhow::Result<String> {
+    let mut buffer = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(unit.as_bytes());
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+    value.serialize(&mut serializer)?;
+    let rendered = String::from_utf8(buffer)?;
+    Ok(rendered.replace('\n', &format!("\n{}", indent)))
+}
+
+/// The indentation step of the file, taken from the first member of the root object.
+fn json_indent_unit(content: &str, root: &JsonObject) -> String {
+    root.members
+        .first()
+        .filter(|member| starts_line(content, member.start))
+        .map(|member| line_indent(content, member.start))
+        .filter(|indent| !indent.is_empty())
+        .unwrap_or("  ")
+        .to_string()
+}
+
+/// Leading whitespace of the line containing `pos`.
+fn line_indent(content: &str, pos: usize) -> &str {
+    let start = content[..pos].rfind('\n').map_or(0, |index| index + 1);
+    let line = &content[start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Whether only whitespace comes before `pos` on its line.
+fn starts_line(content: &str, pos: usize) -> bool {
+    let start = content[..pos].rfind('\n').map_or(0, |index| index + 1);
+    content[start..pos].trim().is_empty()
+}
+
+fn splice(content: &str, range: std::ops::Range<usize>, replacement: &str) -> String {
+    let mut updated = String::with_capacity(content.len() + replacement.len());
+    updated.push_str(&content[..range.start]);
+    updated.push_str(replacement);
+    updated.push_str(&content[range.end..]);
+    updated
+}
+
+fn scan_object(content: &str, open: usize) -> anyhow::Result<JsonObject> {
+    let bytes = content.as_bytes();
+    if bytes.get(open) != Some(&b'{') {
+        anyhow::bail!("expected an object at byte {}", open);
+    }
+
+    let mut members = vec![];
+    let mut pos = skip_whitespace(bytes, open + 1);
+    if bytes.get(pos) == Some(&b'}') {
+        return Ok(JsonObject { open, close: pos, members });
+    }
+
+    loop {
+        let start = pos;
+        let key_end = skip_json_value(bytes, start)?;
+        let key: String = serde_json::from_str(&content[start..key_end])
+            .with_context(|| format!("invalid object key at byte {}", start))?;
+        let colon = skip_whitespace(bytes, key_end);
+        if bytes.get(colon) != Some(&b':') {
+            anyhow::bail!("expected ':' at byte {}", colon);
+        }
+        let value_start = skip_whitespace(bytes, colon + 1);
+        let value_end = skip_json_value(bytes, value_start)?;
+        let separator = content[key_end..value_start].to_string();
+        members.push(Member { key, start, separator, value_start, value_end });
+
+        pos = skip_whitespace(bytes, value_end);
+        match bytes.get(pos) {
+            Some(b',') => pos = skip_whitespace(bytes, pos + 1),
+            Some(b'}') => return Ok(JsonObject { open, close: pos, members }),
+            _ => anyhow::bail!("expected ',' or '}}' at byte {}", pos),
+        }
+    }
+}
+
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+        pos += 1;
+    }
+    pos
+}
+
+/// Returns the end of the JSON value starting at `pos`.
+fn skip_json_value(bytes: &[u8], mut pos: usize) -> anyhow::Result<usize> {
+    let mut depth = 0usize;
+    loop {
+        match bytes.get(pos) {
+            None => anyhow::bail!("unexpected end of document"),
+            Some(b'"') => {
+                pos += 1;
+                while let Some(&byte) = bytes.get(pos) {
+                    pos += if byte == b'\\' { 2 } else { 1 };
+                    if byte == b'"' {
+                        break;
+                    }
+                }
+            }
+            Some(b'{' | b'[') => {
+                depth += 1;
+                pos += 1;
+            }
+            Some(b'}' | b']') => {
+                depth = depth.checked_sub(1).context("unbalanced brackets")?;
+                pos += 1;
+            }
+            Some(_) if depth == 0 => {
+                while bytes.get(pos).is_some_and(|b| !b",}] \t\r\n".contains(b)) {
+                    pos += 1;
+                }
+                return Ok(pos);
+            }
+            Some(_) => pos += 1,
+        }
+        if depth == 0 {
+            return Ok(pos);
+        }
+    }
+}
+
+/// Inserts, replaces (`Some`) or removes (`None`) `templates.<name>` in a YAML document.
+///
+/// Only block style mappings are edited, other layouts are rejected.
+pub fn update_yaml_template(
+    content: &str,
+    name: &str,
+    item: Option<&serde_yaml_ng::Value>,
+) -> anyhow::Result<String> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let top_level = |line: &&str| !is_yaml_filler(line) && yaml_indent(line) == 0;
+
+    let Some(header) = lines.iter().position(|line| yaml_key(line) == Some("templates")) else {
+        let Some(item) = item else {
+            return Ok(content.to_string());
+        };
+        if lines.iter().any(|line| !is_yaml_filler(line) && !line.contains(':')) {
+            anyhow::bail!("the config root is not a block mapping");
+        }
+        let mut updated = content.to_string();
+        if !updated.is_empty() && !updated.ends_with('\n') {
+            updated.push('\n');
+        }
+        updated.push_str("templates:\n");
+        updated.push_str(&render_yaml_entry(name, item, 2)?);
+        return Ok(updated);
+    };
+
+    let header_value = yaml_value(lines[header]);
+    let empty_flow = matches!(header_value, "{}" | "null" | "~");
+    if !header_value.is_empty() && !empty_flow {
+        anyhow::bail!("`templates` is not a block mapping");
+    }
+
+    let end =
+        lines[header + 1..].iter().position(top_level).map_or(lines.len(), |i| header + 1 + i);
+    let block: Vec<usize> = (header + 1..end).filter(|&i| !is_yaml_filler(lines[i])).collect();
+    let child_indent = block.first().map_or(2, |&i| yaml_indent(lines[i]));
+    let entries: Vec<usize> =
+        block.iter().copied().filter(|&i| yaml_indent(lines[i]) == child_indent).collect();
+    if block.iter().any(|&i| yaml_indent(lines[i]) < child_indent) {
+        anyhow::bail!("`templates` has inconsistent indentation");
+    }
+
+    let existing = entries.iter().position(|&i| yaml_key(&lines[i][child_indent..]) == Some(name));
+    // The last content line of an entry, comments and blank lines after it stay in place
+    let entry_end = |index: usize| {
+        let next = entries.get(index + 1).copied().unwrap_or(end);
+        block.iter().copied().rfind(|&i| i < next).unwrap_or(entries[index]) + 1
+    };
+
+    let mut updated: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    match (item, existing) {
+        (Some(item), Some(index)) => {
+            let entry = render_yaml_entry(name, item, child_indent)?;
+            updated.splice(entries[index]..entry_end(index), [entry]);
+        }
+        (Some(item), None) => {
+            let entry = render_yaml_entry(name, item, child_indent)?;
+            if empty_flow {
+                updated[header] = with_yaml_value(lines[header], "");
+            }
+            let at = block.last().map_or(header + 1, |&i| i + 1);
+            if !updated[at - 1].ends_with('\n') {
+                updated[at - 1].push('\n');
+            }
+            updated.insert(at, entry);
+        }
+        (None, Some(index)) => {
+            let mut start = entries[index];
+            while start > header + 1
+                && lines[start - 1].trim_start().starts_with('#')
+                && yaml_indent(lines[start - 1]) == child_indent
+            {
+                start -= 1;
+            }
+            updated.drain(start..entry_end(index));
+            // An empty `templates:` would be null, keep it an empty mapping
+            if entries.len() == 1 {
+                updated[header] = with_yaml_value(lines[header], "{}");
+            }
+        }
+        (None, None) => {}
+    }
+
+    Ok(updated.concat())
+}
+
+fn render_yaml_entry(
+    name: &str,
+    item: &serde_yaml_ng::Value,
+    indent: usize,
+) -> anyhow::Result<String> {
+    let mut entry = serde_yaml_ng::Mapping::new();
+    entry.insert(name.into(), item.clone());
+    let rendered = serde_yaml_ng::to_string(&entry)?;
+    let prefix = " ".repeat(indent);
+    Ok(rendered.lines().map(|line| format!("{}{}\n", prefix, line)).collect())
+}
+
+/// Blank lines, comments and document markers.
+fn is_yaml_filler(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" || trimmed == "..."
+}
+
+fn yaml_indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// The key of a `key: value` line without indentation, quotes removed.
+fn yaml_key(line: &str) -> Option<&str> {
+    if line.starts_with([' ', '#']) {
+        return None;
+    }
+    let (key, _) = line
+        .trim_end()
+        .split_once(':')
+        .filter(|(_, rest)| rest.is_empty() || rest.starts_with([' ', '\t']))?;
+    Some(key.trim().trim_matches(|c| c == '"' || c == '\''))
+}
+
+/// Replaces the inline value of a `key: value` line, keeping a trailing comment.
+fn with_yaml_value(line: &str, value: &str) -> String {
+    let (key, rest) = line.split_once(':').unwrap_or((line.trim_end(), ""));
+    let comment = rest.find(" #").map_or("", |index| rest[index..].trim_end());
+    let value = if value.is_empty() { String::new() } else { format!(" {}", value) };
+    format!("{}:{}{}\n", key, value, comment)
+}
+
+/// The inline value of a `key: value` line, without a trailing comment.
+fn yaml_value(line: &str) -> &str {
+    let value = line.split_once(':').map_or("", |(_, value)| value);
+    let value = value.split(" #").next().unwrap_or_default();
+    value.trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item() -> serde_json::Value {
+        serde_json::json!({ "kind": "project", "repo": "octocat/Hello-World" })
+    }
+
+    fn yaml_item() -> serde_yaml_ng::Value {
+        serde_yaml_ng::to_value(item()).unwrap()
+    }
+
+    #[test]
+    fn test_json_insert_keeps_formatting() {
+        let content = "{\n    \"templates\": {\n        \"a\": { \"kind\": \"package\" }\n    },\n    \"watch\": []\n}\n";
+
+        let added = update_json_template(content, "hello", Some(&item())).unwrap();
+
+        assert_eq!(
+            added,
+            "{\n    \"templates\": {\n        \"a\": { \"kind\": \"package\" },\n        \"hello\": {\n            \"kind\": \"project\",\n            \"repo\": \"octocat/Hello-World\"\n        }\n    },\n    \"watch\": []\n}\n"
+        );
+        assert_eq!(update_json_template(&added, "hello", None).unwrap(), content);
+    }
+
+    #[test]
+    fn test_json_replace_remove_and_missing_templates() {
+        let content = r#"{"watch":[],"templates":{"a":{"kind":"package"},"b":{"kind":"package"}}}"#;
+
+        let replaced = update_json_template(content, "a", Some(&item())).unwrap();
+        assert_eq!(
+            replaced,
+            r#"{"watch":[],"templates":{"a":{"kind":"project","repo":"octocat/Hello-World"},"b":{"kind":"package"}}}"#
+        );
+        let removed = update_json_template(content, "a", None).unwrap();
+        assert_eq!(removed, r#"{"watch":[],"templates":{"b":{"kind":"package"}}}"#);
+        let removed = update_json_template(&removed, "b", None).unwrap();
+        assert_eq!(removed, r#"{"watch":[],"templates":{}}"#);
+
+        let added = update_json_template("{\n  \"watch\": []\n}\n", "a", Some(&item())).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&added).unwrap();
+        assert_eq!(value["templates"]["a"], item());
+        assert!(added.starts_with("{\n  \"watch\": [],\n  \"templates\": {\n    \"a\": {\n"));
+    }
+
+    #[test]
+    fn test_yaml_keeps_comments_and_order() {
+        let content = "# shared templates\ntemplates:\n  # the basic one\n  basic:\n    kind: package # local\n\n# other settings\nhosts:\n  git.example.com: gitlab\n";
+
+        let added = update_yaml_template(content, "hello", Some(&yaml_item())).unwrap();
+        assert_eq!(
+            added,
+            "# shared templates\ntemplates:\n  # the basic one\n  basic:\n    kind: package # local\n  hello:\n    kind: project\n    repo: octocat/Hello-World\n\n# other settings\nhosts:\n  git.example.com: gitlab\n"
+        );
+        assert_eq!(update_yaml_template(&added, "hello", None).unwrap(), content);
+
+        let removed = update_yaml_template(content, "basic", None).unwrap();
+        assert_eq!(
+            removed,
+            "# shared templates\ntemplates: {}\n\n# other settings\nhosts:\n  git.example.com: gitlab\n"
+        );
+    }
+
+    #[test]
+    fn test_yaml_empty_and_missing_templates() {
+        let added = update_yaml_template("templates: {}\n", "hello", Some(&yaml_item())).unwrap();
+        assert_eq!(
+            added,
+            "templates:\n  hello:\n    kind: project\n    repo: octocat/Hello-World\n"
+        );
+
+        let added = update_yaml_template("hosts: {}", "hello", Some(&yaml_item())).unwrap();
+        assert_eq!(
+            added,
+            "hosts: {}\ntemplates:\n  hello:\n    kind: project\n    repo: octocat/Hello-World\n"
+        );
+
+        let err =
+            update_yaml_template("templates: { a: { kind: package } }\n", "b", Some(&yaml_item()));
+        assert!(err.unwrap_err().to_string().contains("not a block mapping"));
+    }
+}
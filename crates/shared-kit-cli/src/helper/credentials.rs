@@ -0,0 +1,200 @@
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::AUTHORIZATION;
+
+use crate::constant::Credential;
+use crate::helper::repo::RepoPlatform;
+
+/// Environment variables holding a token, checked in order, per platform
+fn token_env_vars(platform: &RepoPlatform) -> &'static [&'static str] {
+    match platform {
+        RepoPlatform::GitHub => &["GITHUB_TOKEN", "GH_TOKEN"],
+        RepoPlatform::GitLab => &["GITLAB_TOKEN"],
+        RepoPlatform::Gitea => &["GITEA_TOKEN"],
+        RepoPlatform::Forgejo => &["FORGEJO_TOKEN"],
+        RepoPlatform::Bitbucket => &["BITBUCKET_TOKEN"],
+        RepoPlatform::Other(_) => &[],
+    }
+}
+
+/// Finds the credential for a host.
+///
+/// The `[credentials]` config section wins, then the platform token env var
+/// (`GITHUB_TOKEN`, `GITLAB_TOKEN`, ...), then a `machine` entry in `~/.netrc`.
+///
+/// The env vars are only sent to the public host of the platform and to hosts listed in
+/// `[hosts]`, a host that merely looks like a platform never receives them.
+pub fn resolve_credential(
+    platform: &RepoPlatform,
+    host: &str,
+    hosts: &HashMap<String, String>,
+    configured: &HashMap<String, Credential>,
+) -> Option<Credential> {
+    if let Some(credential) = configured.get(host) {
+        return Some(credential.clone());
+    }
+
+    let trusted = platform.public_host() == Some(host) || hosts.contains_key(host);
+    let env_vars = if trusted { token_env_vars(platform) } else { &[] };
+    let from_env =
+        env_vars.iter().find_map(|name| env::var(name).ok().filter(|v| !v.trim().is_empty()));
+    if let Some(token) = from_env {
+        let username = match platform {
+            RepoPlatform::Bitbucket => env::var("BITBUCKET_USERNAME").ok(),
+            _ => None,
+        };
+        return Some(Credential { username, token });
+    }
+
+    let netrc = fs::read_to_string(netrc_path()?).ok()?;
+    netrc_credential(&netrc, host_without_port(host))
+}
+
+/// Adds the platform's auth header to a request.
+///
+/// The token only ever goes into headers, never into URLs, so it cannot leak through error
+/// messages that print the request URL.
+pub fn authorize(
+    request: RequestBuilder,
+    platform: &RepoPlatform,
+    credential: &Credential,
+) -> RequestBuilder {
+    let token = &credential.token;
+    match (platform, &credential.username) {
+        (RepoPlatform::GitLab, None) => request.header("PRIVATE-TOKEN", token),
+        (RepoPlatform::Gitea | RepoPlatform::Forgejo, None) => {
+            request.header(AUTHORIZATION, format!("token {}", token))
+        }
+        (RepoPlatform::GitHub | RepoPlatform::Bitbucket | RepoPlatform::Other(_), None) => {
+            request.bearer_auth(token)
+        }
+        (_, Some(username)) => request.basic_auth(username, Some(token)),
+    }
+}
+
+fn netrc_path() -> Option<PathBuf> {
+    match env::var_os("NETRC") {
+        Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => dirs::home_dir().map(|home| home.join(".netrc")),
+    }
+}
+
+fn host_without_port(host: &str) -> &str {
+    host.split_once(':').map_or(host, |(name, _)| name)
+}
+
+/// Reads the `login` / `password` of a host from `.netrc` content, falling back to `default`.
+pub fn netrc_credential(content: &str, host: &str) -> Option<Credential> {
+    let mut tokens = content.split_whitespace();
+    // Some(true) inside the matching `machine`, Some(false) inside `default`
+    let mut section: Option<bool> = None;
+    let mut host_entry: (Option<String>, Option<String>) = (None, None);
+    let mut default_entry: (Option<String>, Option<String>) = (None, None);
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => section = tokens.next().filter(|name| *name == host).map(|_| true),
+            "default" => section = Some(false),
+            "login" | "password" => {
+                let value = tokens.next().map(String::from);
+                let entry = match section {
+                    Some(true) => &mut host_entry,
+                    Some(false) => &mut default_entry,
+                    None => continue,
+                };
+                if token == "login" {
+                    entry.0 = value;
+                } else {
+                    entry.1 = value;
+                }
+            }
+            "account" => {
+                tokens.next();
+            }
+            "macdef" => section = None,
+            _ => {}
+        }
+    }
+
+    let (username, token) = if host_entry.1.is_some() { host_entry } else { default_entry };
+    Some(Credential { username, token: token? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::blocking::Client;
+
+    fn credential(username: Option<&str>, token: &str) -> Credential {
+        Credential { username: username.map(String::from), token: token.to_string() }
+    }
+
+    fn auth_headers(platform: RepoPlatform, credential: &Credential) -> Vec<(String, String)> {
+        let request = authorize(Client::new().get("http://localhost/"), &platform, credential);
+        let request = request.build().unwrap();
+        request
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_authorize_uses_platform_headers() {
+        let token = credential(None, "secret");
+        let header = |name: &str, value: &str| vec![(name.to_string(), value.to_string())];
+
+        assert_eq!(
+            auth_headers(RepoPlatform::GitHub, &token),
+            header("authorization", "Bearer secret")
+        );
+        assert_eq!(auth_headers(RepoPlatform::GitLab, &token), header("private-token", "secret"));
+        assert_eq!(
+            auth_headers(RepoPlatform::Forgejo, &token),
+            header("authorization", "token secret")
+        );
+        assert_eq!(
+            auth_headers(RepoPlatform::Bitbucket, &credential(Some("me"), "pw")),
+            header("authorization", "Basic bWU6cHc=")
+        );
+    }
+
+    #[test]
+    fn test_config_credential_wins() {
+        let configured =
+            HashMap::from([("git.example.com:8443".to_string(), credential(None, "from-config"))]);
+
+        let found = resolve_credential(
+            &RepoPlatform::GitLab,
+            "git.example.com:8443",
+            &HashMap::new(),
+            &configured,
+        )
+        .unwrap();
+
+        assert_eq!(found.token, "from-config");
+    }
+
+    #[test]
+    fn test_netrc_credential() {
+        let netrc = "machine other.com login a password b\n\
+                     machine git.example.com\n  login deploy\n  password s3cret\n\
+                     default login anon password guest\n";
+
+        assert_eq!(
+            netrc_credential(netrc, "git.example.com"),
+            Some(credential(Some("deploy"), "s3cret"))
+        );
+        assert_eq!(netrc_credential(netrc, "unknown.com"), Some(credential(Some("anon"), "guest")));
+        assert_eq!(netrc_credential("machine a.com login x", "a.com"), None);
+    }
+
+    #[test]
+    fn test_credential_debug_hides_token() {
+        let debug = format!("{:?}", credential(Some("me"), "super-secret"));
+
+        assert!(!debug.contains("super-secret"));
+        assert!(debug.contains("***"));
+    }
+}
@@ -1,12 +1,17 @@
 use std::{
+    collections::HashMap,
+    ffi::OsStr,
     fs,
-    io::{Read, Write},
-    path::{Path, PathBuf},
+    io::Write,
+    path::{Component, Path, PathBuf},
 };
 
 use anyhow::{Context, Ok};
 use serde::{Deserialize, Serialize};
 
+use crate::helper::path::real_path;
+use crate::helper::path_matcher::PathFilter;
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum FileTransformKind {
     Skip,
@@ -14,15 +19,45 @@ pub enum FileTransformKind {
     Rename(String),
     Overwrite { new_content: String, new_name: String },
     NoChange,
+    Error(String),
 }
 
 pub type TransformCallback = dyn Fn(&str, &Path) -> FileTransformKind;
+pub type NameRenderer = dyn Fn(&str) -> anyhow::Result<String>;
+
+/// Number of leading bytes searched for a NUL byte when sniffing binary content
+const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Options shared by the directory and file copy functions.
+#[derive(Default, Clone, Copy)]
+pub struct CopyOptions<'a> {
+    /// Callback that transforms, renames or skips files
+    pub callback: Option<&'a TransformCallback>,
+    /// Forces matching files to be copied as binary
+    pub force_binary: Option<&'a dyn Fn(&Path) -> bool>,
+    /// Prunes excluded directories and decides which empty directories are kept
+    pub filter: Option<&'a PathFilter>,
+    /// Renders each file and directory name, e.g. `src/{{crate_name}}/lib.rs`
+    pub render_name: Option<&'a NameRenderer>,
+}
+
+/// Source of every target written by a directory copy, so two sources whose names render
+/// to the same path are reported instead of overwriting each other.
+type ClaimedTargets = HashMap<PathBuf, PathBuf>;
+
+/// Checks whether file content should be treated as binary.
+///
+/// Content is binary when its first bytes contain a NUL byte or when it is not valid UTF-8.
+pub fn is_binary_content(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0) || std::str::from_utf8(bytes).is_err()
+}
 
 /// Recursively counts the number of files (not directories) under a given path.
 ///
 /// # Arguments
 ///
 /// * `path` - The root directory path to start counting from.
+/// * `filter` - Optional includes/excludes filter, filtered out files are not counted.
 ///
 /// # Returns
 ///
@@ -30,28 +65,37 @@ pub type TransformCallback = dyn Fn(&str, &Path) -> FileTransformKind;
 ///
 /// # Examples
 ///
-/// ```rust
-/// let count = pre_count_files(&PathBuf::from("./some_folder")).unwrap();
+/// ```ignore
+/// let count = pre_count_files(&PathBuf::from("./some_folder"), None).unwrap();
 /// println!("Total files: {}", count);
 /// ```
-pub fn pre_count_files(path: &PathBuf) -> anyhow::Result<usize> {
-    fn count_recursive(path: &Path, count: &mut usize) -> anyhow::Result<()> {
+pub fn pre_count_files(path: &Path, filter: Option<&PathFilter>) -> anyhow::Result<usize> {
+    fn count_recursive(
+        root: &Path,
+        path: &Path,
+        filter: Option<&PathFilter>,
+        count: &mut usize,
+    ) -> anyhow::Result<()> {
         for entry in fs::read_dir(path)
             .map_err(|e| anyhow::anyhow!("Failed to read dir '{}': {}", path.display(), e))?
         {
             let entry = entry?;
             let path = entry.path();
-            if path.is_file() {
-                *count += 1;
-            } else if path.is_dir() {
-                count_recursive(&path, count)?;
+            let relative_path = path.strip_prefix(root).unwrap();
+            let file_type = entry.file_type()?;
+            if file_type.is_file() || file_type.is_symlink() {
+                if filter.is_none_or(|f| f.is_file_allowed(relative_path)) {
+                    *count += 1;
+                }
+            } else if file_type.is_dir() && filter.is_none_or(|f| f.is_dir_allowed(relative_path)) {
+                count_recursive(root, &path, filter, count)?;
             }
         }
         Ok(())
     }
 
     let mut count = 0;
-    count_recursive(path, &mut count)?;
+    count_recursive(path, path, filter, &mut count)?;
     Ok(count)
 }
 
@@ -68,10 +112,15 @@ pub fn pre_count_files(path: &PathBuf) -> anyhow::Result<usize> {
 ///
 /// # Examples
 ///
-/// ```rust
+/// ```ignore
 /// write_file(Path::new("./output.txt"), "Hello, world!")?;
 /// ```
 pub fn write_file(target: &Path, content: &str) -> anyhow::Result<()> {
+    write_file_bytes(target, content.as_bytes())
+}
+
+/// Writes raw bytes to the target file, creating parent directories if needed.
+pub fn write_file_bytes(target: &Path, content: &[u8]) -> anyhow::Result<()> {
     if let Some(parent) = target.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("Failed to create parent directory: {}", parent.display()))?;
@@ -80,72 +129,45 @@ pub fn write_file(target: &Path, content: &str) -> anyhow::Result<()> {
     let mut file = fs::File::create(target)
         .with_context(|| format!("Failed to create target file: {}", target.display()))?;
 
-    file.write_all(content.as_bytes())
+    file.write_all(content)
         .with_context(|| format!("Failed to write to target file: {}", target.display()))?;
 
     Ok(())
 }
 
-/// Reads the entire content of a file into a `String`.
-///
-/// # Arguments
-///
-/// * `origin` - Path to the source file to read.
-///
-/// # Returns
-///
-/// A `String` containing the file content, or an error if reading fails.
-///
-/// # Examples
-///
-/// ```rust
-/// let content = read_file(Path::new("./input.txt"))?;
-/// println!("{}", content);
-/// ```
-pub fn read_file(origin: &Path) -> anyhow::Result<String> {
-    let mut content = String::new();
-
-    fs::File::open(origin)
-        .with_context(|| format!("Failed to open source file: {}", origin.display()))?
-        .read_to_string(&mut content)
-        .with_context(|| format!("Failed to read from source file: {}", origin.display()))?;
-
-    Ok(content)
-}
-
 /// Recursively copies a directory's contents to a target path, optionally transforming file contents.
 ///
 /// # Arguments
 ///
 /// * `origin` - Source directory path.
 /// * `target` - Destination directory path.
-/// * `callback` - Optional callback that returns either a modified string or skip signal.
+/// * `options` - Callback, binary detection, filter and name rendering used for every entry.
 ///
 /// # Behavior
 ///
 /// - Preserves directory structure.
 /// - Skips files if `FileTransformKind::Skip` is returned from the callback.
 /// - Replaces file content if `FileTransformKind::Replace(String)` is returned.
+/// - Fails with the message of `FileTransformKind::Error(String)`.
 ///
-/// # Examples
+/// Symlinks are recreated as symlinks instead of being followed, and are rejected when they
+/// point outside of `origin`. Empty directories are kept unless the filter leaves them out.
+/// Fails when two entries end up at the same target after renaming.
 ///
-/// ```rust
-/// use std::path::PathBuf;
+/// # Examples
 ///
+/// ```ignore
 /// let transform = |content: &str, path: &Path| {
 ///     FileTransformKind::Replace(content.replace("old", "new"))
 /// };
+/// let options = CopyOptions { callback: Some(&transform), ..Default::default() };
 ///
-/// copy_directory_with_replace(
-///     &PathBuf::from("./src"),
-///     &PathBuf::from("./dst"),
-///     Some(&transform),
-/// )?;
+/// copy_directory_with_options(Path::new("./src"), Path::new("./dst"), &options)?;
 /// ```
-pub fn copy_directory_with_replace(
-    origin: &PathBuf,
-    target: &PathBuf,
-    callback: Option<&TransformCallback>,
+pub fn copy_directory_with_options(
+    origin: &Path,
+    target: &Path,
+    options: &CopyOptions,
 ) -> anyhow::Result<()> {
     if !origin.is_dir() {
         let err_msg = format!("Source path is not a directory: {}", origin.display());
@@ -153,40 +175,101 @@ pub fn copy_directory_with_replace(
         anyhow::bail!(err_msg);
     }
 
-    for entry in fs::read_dir(origin)
+    copy_directory_recursive(origin, origin, target, options, &mut ClaimedTargets::new())
+}
+
+fn copy_directory_recursive(
+    root: &Path,
+    origin: &Path,
+    target: &Path,
+    options: &CopyOptions,
+    claimed: &mut ClaimedTargets,
+) -> anyhow::Result<()> {
+    let entries = fs::read_dir(origin)
         .with_context(|| format!("Failed to read directory: {}", origin.display()))?
-    {
-        let entry = entry?;
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if entries.is_empty() {
+        let relative_path = origin.strip_prefix(root).unwrap();
+        let keep = relative_path.as_os_str().is_empty()
+            || options.filter.is_none_or(|f| f.is_file_allowed(relative_path));
+        if keep {
+            fs::create_dir_all(target)
+                .with_context(|| format!("Failed to create directory: {}", target.display()))?;
+        }
+        return Ok(());
+    }
+
+    for entry in entries {
         let path = entry.path();
-        let relative_path = path.strip_prefix(origin).unwrap();
-        let target_path = target.join(relative_path);
-
-        if path.is_dir() {
-            copy_directory_with_replace(&path, &target_path, callback)?;
-        } else if path.is_file() {
-            copy_with_replace(&path, &target_path, callback)
-                .with_context(|| format!("Failed to copy file: {}", path.display()))?;
+        let relative_path = path.strip_prefix(root).unwrap();
+        let target_path =
+            target.join(render_entry_name(&entry.file_name(), relative_path, options)?);
+        let file_type = entry.file_type()?;
+
+        if file_type.is_symlink() {
+            copy_symlink(root, &path, &target_path, options, Some(claimed))
+                .with_context(|| format!("Failed to copy symlink: {}", path.display()))?;
+        } else if file_type.is_dir() {
+            if options.filter.is_none_or(|f| f.is_dir_allowed(relative_path)) {
+                copy_directory_recursive(root, &path, &target_path, options, claimed)?;
+            }
+        } else if file_type.is_file() {
+            copy_file(&path, &target_path, options, Some(claimed))?;
         }
     }
 
     Ok(())
 }
 
+/// Renders a directory entry name with `options.render_name`.
+///
+/// The rendered name may contain `/` to create nested directories, but has to stay inside the
+/// parent directory.
+fn render_entry_name(
+    name: &OsStr,
+    relative_path: &Path,
+    options: &CopyOptions,
+) -> anyhow::Result<PathBuf> {
+    let (Some(render), Some(name)) = (options.render_name, name.to_str()) else {
+        return Ok(PathBuf::from(name));
+    };
+
+    let rendered = render(name).map_err(|err| {
+        anyhow::anyhow!("❌ Template error in the name of '{}': {}", relative_path.display(), err)
+    })?;
+    let rendered = PathBuf::from(rendered);
+    let mut components = rendered.components().peekable();
+    if components.peek().is_none() || !components.all(|c| matches!(c, Component::Normal(_))) {
+        anyhow::bail!(
+            "❌ '{}' renders to the invalid name '{}'",
+            relative_path.display(),
+            rendered.display()
+        );
+    }
+    Ok(rendered)
+}
+
 /// Copies a single file, optionally transforming or skipping its content.
 ///
 /// # Arguments
 ///
 /// * `origin` - Path to the source file.
 /// * `target` - Destination file path.
-/// * `callback` - Optional callback that transforms or skips the content.
+/// * `options` - Options whose callback transforms, renames or skips the content.
+/// * `claimed` - Targets already written by a directory copy, `None` for a single file.
 ///
 /// # Returns
 ///
 /// Returns `Ok(())` if the operation succeeds, or an error otherwise.
 ///
+/// Binary files are copied byte-for-byte. The callback still sees them, with empty content,
+/// so `Skip` and the new name of `Rename` / `Overwrite` apply while content changes are ignored.
+/// Permission bits of the source file are kept.
+///
 /// # Examples
 ///
-/// ```rust
+/// ```ignore
 /// let transform = |content: &str, _path: &Path| {
 ///     if content.contains("ignore") {
 ///         FileTransformKind::Skip
@@ -194,41 +277,166 @@ pub fn copy_directory_with_replace(
 ///         FileTransformKind::Replace(content.to_string())
 ///     }
 /// };
+/// let options = CopyOptions { callback: Some(&transform), ..Default::default() };
 ///
-/// copy_with_replace(Path::new("a.txt"), Path::new("b.txt"), Some(&transform))?;
+/// copy_file(Path::new("a.txt"), Path::new("b.txt"), &options, None)?;
 /// ```
-pub fn copy_with_replace(
+fn copy_file(
     origin: &Path,
     target: &Path,
-    callback: Option<&TransformCallback>,
+    options: &CopyOptions,
+    claimed: Option<&mut ClaimedTargets>,
 ) -> anyhow::Result<()> {
-    let content = read_file(origin)?;
+    let bytes = fs::read(origin)
+        .with_context(|| format!("Failed to read from source file: {}", origin.display()))?;
+    let permissions = fs::metadata(origin)
+        .with_context(|| format!("Failed to read metadata: {}", origin.display()))?
+        .permissions();
+
+    let is_binary = options.force_binary.is_some_and(|f| f(origin)) || is_binary_content(&bytes);
+    let content = if is_binary {
+        ""
+    } else {
+        std::str::from_utf8(&bytes)
+            .with_context(|| format!("Failed to read from source file: {}", origin.display()))?
+    };
+
+    let Some((target, new_content)) = apply_transform(content, origin, target, options, claimed)?
+    else {
+        return Ok(());
+    };
+
+    match new_content {
+        Some(new_content) if !is_binary => write_file(&target, &new_content)?,
+        _ => write_file_bytes(&target, &bytes)?,
+    }
+
+    fs::set_permissions(&target, permissions)
+        .with_context(|| format!("Failed to set permissions: {}", target.display()))?;
 
-    let transform_result = match callback {
-        Some(cb) => cb(&content, origin),
+    Ok(())
+}
+
+/// Recreates a symlink at the target, keeping it relative to the template.
+///
+/// Like binary files, the callback sees symlinks with empty content so they can be skipped
+/// or renamed.
+fn copy_symlink(
+    root: &Path,
+    origin: &Path,
+    target: &Path,
+    options: &CopyOptions,
+    claimed: Option<&mut ClaimedTargets>,
+) -> anyhow::Result<()> {
+    let link = fs::read_link(origin)
+        .with_context(|| format!("Failed to read symlink: {}", origin.display()))?;
+    // Compare real paths, a link may point through other symlinks of the template
+    let root = real_path(root)?;
+    let link_dir = real_path(origin.parent().unwrap_or(&root))?;
+    let resolved = real_path(&link_dir.join(&link))?;
+
+    if !resolved.starts_with(&root) {
+        anyhow::bail!(
+            "❌ Symlink '{}' points outside the template: '{}'",
+            origin.display(),
+            link.display()
+        );
+    }
+
+    let link = if link.is_absolute() { relative_path_between(&link_dir, &resolved) } else { link };
+
+    let Some((target, _)) = apply_transform("", origin, target, options, claimed)? else {
+        return Ok(());
+    };
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create parent directory: {}", parent.display()))?;
+    }
+    if fs::symlink_metadata(&target).is_ok() {
+        fs::remove_file(&target)
+            .with_context(|| format!("Failed to replace existing file: {}", target.display()))?;
+    }
+
+    create_symlink(&link, &target, resolved.is_dir())
+        .with_context(|| format!("Failed to create symlink: {}", target.display()))
+}
+
+/// Runs the callback and returns the final target path and the replaced content,
+/// or `None` when the file is skipped.
+///
+/// The final target is recorded in `claimed`, failing when another source already claimed it.
+fn apply_transform(
+    content: &str,
+    origin: &Path,
+    target: &Path,
+    options: &CopyOptions,
+    claimed: Option<&mut ClaimedTargets>,
+) -> anyhow::Result<Option<(PathBuf, Option<String>)>> {
+    let transform_result = match options.callback {
+        Some(cb) => cb(content, origin),
         None => FileTransformKind::NoChange,
     };
 
-    match transform_result {
+    let (new_name, new_content) = match transform_result {
         FileTransformKind::Skip => {
-            info_msg!("Skipped file: {}", origin.display());
-            return Ok(());
-        }
-        FileTransformKind::Rename(new_name) => {
-            let new_target = target.with_file_name(new_name);
-            write_file(&new_target, &content)?
+            tracing::debug!("Skipped file: {}", origin.display());
+            return Ok(None);
         }
-        FileTransformKind::Replace(new_content) => write_file(target, &new_content)?,
+        FileTransformKind::Rename(new_name) => (Some(new_name), None),
+        FileTransformKind::Replace(new_content) => (None, Some(new_content)),
         FileTransformKind::Overwrite { new_content, new_name } => {
-            let new_target = target.with_file_name(new_name);
-            write_file(&new_target, &new_content)?;
-        }
-        FileTransformKind::NoChange => {
-            write_file(target, &content)?;
+            (Some(new_name), Some(new_content))
         }
+        FileTransformKind::NoChange => (None, None),
+        FileTransformKind::Error(message) => anyhow::bail!(message),
+    };
+
+    let target = match new_name {
+        Some(new_name) => target.with_file_name(new_name),
+        None => target.to_path_buf(),
+    };
+
+    if let Some(previous) = claimed.and_then(|c| c.insert(target.clone(), origin.to_path_buf())) {
+        anyhow::bail!(
+            "❌ Name collision: '{}' and '{}' both render to '{}'",
+            previous.display(),
+            origin.display(),
+            target.display()
+        );
     }
 
-    Ok(())
+    Ok(Some((target, new_content)))
+}
+
+/// Builds the relative path leading from the `from` directory to `to`.
+fn relative_path_between(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+#[cfg(unix)]
+fn create_symlink(link: &Path, target: &Path, _is_dir: bool) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(link, target)
+}
+
+#[cfg(windows)]
+fn create_symlink(link: &Path, target: &Path, is_dir: bool) -> std::io::Result<()> {
+    if is_dir {
+        std::os::windows::fs::symlink_dir(link, target)
+    } else {
+        std::os::windows::fs::symlink_file(link, target)
+    }
 }
 
 #[cfg(test)]
@@ -249,7 +457,7 @@ mod tests {
     #[test]
     fn test_pre_count_files_empty() {
         let dir = tempdir().unwrap();
-        assert_eq!(pre_count_files(&dir.path().to_path_buf()).unwrap(), 0);
+        assert_eq!(pre_count_files(dir.path(), None).unwrap(), 0);
     }
 
     /// Test that `pre_count_files` correctly counts nested files.
@@ -258,14 +466,29 @@ mod tests {
         let dir = tempdir().unwrap();
         create_file(&dir.path().join("a.txt"), "a");
         create_file(&dir.path().join("sub/b.txt"), "b");
-        assert_eq!(pre_count_files(&dir.path().to_path_buf()).unwrap(), 2);
+        assert_eq!(pre_count_files(dir.path(), None).unwrap(), 2);
+    }
+
+    /// Test that `pre_count_files` leaves out filtered files and directories.
+    #[test]
+    fn test_pre_count_files_with_filter() {
+        let dir = tempdir().unwrap();
+        create_file(&dir.path().join("a.txt"), "a");
+        create_file(&dir.path().join("b.swp"), "b");
+        create_file(&dir.path().join("node_modules/pkg/index.js"), "c");
+        create_file(&dir.path().join("src/d.txt"), "d");
+
+        let filter =
+            PathFilter::new(&[], &["/node_modules".to_string(), "regex:\\.swp$".to_string()])
+                .unwrap();
+        assert_eq!(pre_count_files(dir.path(), Some(&filter)).unwrap(), 2);
     }
 
     /// Test that `pre_count_files` fails on a nonexistent path.
     #[test]
     fn test_pre_count_files_invalid_path() {
         let path = PathBuf::from("nonexistent_dir_should_fail");
-        assert!(pre_count_files(&path).is_err());
+        assert!(pre_count_files(&path, None).is_err());
     }
 
     /// Test writing and reading back a file.
@@ -276,13 +499,13 @@ mod tests {
 
         let content = "Hello, world!";
         write_file(&file_path, content).unwrap();
-        let read_back = read_file(&file_path).unwrap();
+        let read_back = fs::read_to_string(&file_path).unwrap();
         assert_eq!(read_back, content);
     }
 
-    /// Test that `copy_with_replace` performs content replacement.
+    /// Test that `copy_file` performs content replacement.
     #[test]
-    fn test_copy_with_replace_replace() {
+    fn test_copy_file_replace() {
         let dir = tempdir().unwrap();
         let origin = dir.path().join("origin.txt");
         let target = dir.path().join("target.txt");
@@ -290,15 +513,21 @@ mod tests {
         create_file(&origin, "original");
 
         let transform = |_: &str, _: &Path| FileTransformKind::Replace("replaced".to_string());
-        copy_with_replace(&origin, &target, Some(&transform)).unwrap();
+        copy_file(
+            &origin,
+            &target,
+            &CopyOptions { callback: Some(&transform), ..Default::default() },
+            None,
+        )
+        .unwrap();
 
         let result = fs::read_to_string(&target).unwrap();
         assert_eq!(result, "replaced");
     }
 
-    /// Test that `copy_with_replace` skips the file when `Skip` is returned.
+    /// Test that `copy_file` skips the file when `Skip` is returned.
     #[test]
-    fn test_copy_with_replace_skip() {
+    fn test_copy_file_skip() {
         let dir = tempdir().unwrap();
         let origin = dir.path().join("origin.txt");
         let target = dir.path().join("target.txt");
@@ -306,14 +535,42 @@ mod tests {
         create_file(&origin, "original");
 
         let transform = |_: &str, _: &Path| FileTransformKind::Skip;
-        copy_with_replace(&origin, &target, Some(&transform)).unwrap();
+        copy_file(
+            &origin,
+            &target,
+            &CopyOptions { callback: Some(&transform), ..Default::default() },
+            None,
+        )
+        .unwrap();
+
+        assert!(!target.exists());
+    }
 
+    /// Test that `copy_file` fails with the message of `Error`.
+    #[test]
+    fn test_copy_file_error() {
+        let dir = tempdir().unwrap();
+        let origin = dir.path().join("origin.txt");
+        let target = dir.path().join("target.txt");
+
+        create_file(&origin, "original");
+
+        let transform = |_: &str, _: &Path| FileTransformKind::Error("broken".to_string());
+        let err = copy_file(
+            &origin,
+            &target,
+            &CopyOptions { callback: Some(&transform), ..Default::default() },
+            None,
+        )
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "broken");
         assert!(!target.exists());
     }
 
-    /// Test that `copy_directory_with_replace` recursively copies and transforms files.
+    /// Test that `copy_directory_with_options` recursively copies and transforms files.
     #[test]
-    fn test_copy_directory_with_replace_basic() {
+    fn test_copy_directory_basic() {
         let dir = tempdir().unwrap();
         let src = dir.path().join("src");
         let dst = dir.path().join("dst");
@@ -323,33 +580,32 @@ mod tests {
 
         let transform = |_: &str, _: &Path| FileTransformKind::Replace("baz".to_string());
 
-        copy_directory_with_replace(&src, &dst, Some(&transform)).unwrap();
+        copy_directory_with_options(
+            &src,
+            &dst,
+            &CopyOptions { callback: Some(&transform), ..Default::default() },
+        )
+        .unwrap();
 
         assert_eq!(fs::read_to_string(dst.join("a.txt")).unwrap(), "baz");
         assert_eq!(fs::read_to_string(dst.join("nested/b.txt")).unwrap(), "baz");
     }
 
-    /// Test `copy_directory_with_replace` when source is not a directory.
+    /// Test `copy_directory_with_options` when source is not a directory.
     #[test]
-    fn test_copy_directory_with_replace_invalid_source() {
+    fn test_copy_directory_invalid_source() {
         let dir = tempdir().unwrap();
         let file = dir.path().join("not_a_dir.txt");
         create_file(&file, "invalid");
 
-        let result = copy_directory_with_replace(&file, &dir.path().join("out"), None);
+        let result =
+            copy_directory_with_options(&file, &dir.path().join("out"), &CopyOptions::default());
         assert!(result.is_err());
     }
 
-    /// Test `read_file` with invalid file path.
-    #[test]
-    fn test_read_file_invalid() {
-        let path = PathBuf::from("nonexistent_file.txt");
-        assert!(read_file(&path).is_err());
-    }
-
-    /// Test that `copy_with_replace` handles Overwrite variant.
+    /// Test that `copy_file` handles Overwrite variant.
     #[test]
-    fn test_copy_with_replace_overwrite() {
+    fn test_copy_file_overwrite() {
         let dir = tempdir().unwrap();
         let origin = dir.path().join("origin.txt");
         let target = dir.path().join("target.txt");
@@ -359,44 +615,319 @@ mod tests {
             new_content: "overwritten".to_string(),
             new_name: new_name.to_string(),
         };
-        copy_with_replace(&origin, &target, Some(&transform)).unwrap();
+        copy_file(
+            &origin,
+            &target,
+            &CopyOptions { callback: Some(&transform), ..Default::default() },
+            None,
+        )
+        .unwrap();
         let renamed_path = target.with_file_name(new_name);
         assert_eq!(fs::read_to_string(renamed_path).unwrap(), "overwritten");
     }
 
-    /// Test that `copy_with_replace` handles Rename variant.
+    /// Test that `copy_file` handles Rename variant.
     #[test]
-    fn test_copy_with_replace_rename() {
+    fn test_copy_file_rename() {
         let dir = tempdir().unwrap();
         let origin = dir.path().join("origin.txt");
         let target = dir.path().join("target.txt");
         let new_name = "renamed.txt";
         create_file(&origin, "original");
         let transform = |_: &str, _: &Path| FileTransformKind::Rename(new_name.to_string());
-        copy_with_replace(&origin, &target, Some(&transform)).unwrap();
+        copy_file(
+            &origin,
+            &target,
+            &CopyOptions { callback: Some(&transform), ..Default::default() },
+            None,
+        )
+        .unwrap();
         let renamed_path = target.with_file_name(new_name);
         assert_eq!(fs::read_to_string(renamed_path).unwrap(), "original");
     }
 
-    /// Test that `copy_with_replace` works with callback None (NoChange).
+    /// Test that `copy_file` works with callback None (NoChange).
     #[test]
-    fn test_copy_with_replace_no_callback() {
+    fn test_copy_file_no_callback() {
         let dir = tempdir().unwrap();
         let origin = dir.path().join("origin.txt");
         let target = dir.path().join("target.txt");
         create_file(&origin, "original");
-        copy_with_replace(&origin, &target, None).unwrap();
+        copy_file(&origin, &target, &CopyOptions { callback: None, ..Default::default() }, None)
+            .unwrap();
         assert_eq!(fs::read_to_string(&target).unwrap(), "original");
     }
 
-    /// Test that `copy_directory_with_replace` creates target directories automatically.
+    /// Test that `copy_directory_with_options` creates target directories automatically.
     #[test]
-    fn test_copy_directory_with_replace_creates_target_dirs() {
+    fn test_copy_directory_creates_target_dirs() {
         let dir = tempdir().unwrap();
         let src = dir.path().join("src");
         let dst = dir.path().join("dst/nested/dir");
         create_file(&src.join("a.txt"), "foo");
-        copy_directory_with_replace(&src, &dst, None).unwrap();
+        copy_directory_with_options(
+            &src,
+            &dst,
+            &CopyOptions { callback: None, ..Default::default() },
+        )
+        .unwrap();
         assert_eq!(fs::read_to_string(dst.join("a.txt")).unwrap(), "foo");
     }
+
+    const PNG_HEADER: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0xFF];
+
+    /// Test binary detection on NUL bytes and invalid UTF-8.
+    #[test]
+    fn test_is_binary_content() {
+        assert!(is_binary_content(PNG_HEADER));
+        assert!(is_binary_content(&[b'a', 0xFF, b'b']));
+        assert!(is_binary_content(b"text\0more"));
+        assert!(!is_binary_content("héllo {{name}}".as_bytes()));
+        assert!(!is_binary_content(b""));
+    }
+
+    /// Test that binary files are copied byte-for-byte and ignore content transforms.
+    #[test]
+    fn test_copy_file_binary_keeps_bytes() {
+        let dir = tempdir().unwrap();
+        let origin = dir.path().join("logo.png");
+        let target = dir.path().join("out/logo.png");
+        fs::write(&origin, PNG_HEADER).unwrap();
+
+        let transform = |content: &str, _: &Path| {
+            assert!(content.is_empty());
+            FileTransformKind::Replace("replaced".to_string())
+        };
+        copy_file(
+            &origin,
+            &target,
+            &CopyOptions { callback: Some(&transform), ..Default::default() },
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(fs::read(&target).unwrap(), PNG_HEADER);
+    }
+
+    /// Test that binary files still honour `Skip` and renames.
+    #[test]
+    fn test_copy_file_binary_skip_and_rename() {
+        let dir = tempdir().unwrap();
+        let origin = dir.path().join("favicon.ico");
+        let target = dir.path().join("favicon.ico.out");
+        fs::write(&origin, PNG_HEADER).unwrap();
+
+        let skip = |_: &str, _: &Path| FileTransformKind::Skip;
+        copy_file(
+            &origin,
+            &target,
+            &CopyOptions { callback: Some(&skip), ..Default::default() },
+            None,
+        )
+        .unwrap();
+        assert!(!target.exists());
+
+        let rename = |_: &str, _: &Path| FileTransformKind::Overwrite {
+            new_content: "ignored".to_string(),
+            new_name: "renamed.ico".to_string(),
+        };
+        copy_file(
+            &origin,
+            &target,
+            &CopyOptions { callback: Some(&rename), ..Default::default() },
+            None,
+        )
+        .unwrap();
+        assert_eq!(fs::read(target.with_file_name("renamed.ico")).unwrap(), PNG_HEADER);
+    }
+
+    /// Test that `force_binary` skips content transforms for text files.
+    #[test]
+    fn test_copy_directory_with_options_force_binary() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dst = dir.path().join("dst");
+        create_file(&src.join("keep.svg"), "<svg>{{name}}</svg>");
+        create_file(&src.join("index.txt"), "{{name}}");
+
+        let transform = |content: &str, _: &Path| {
+            FileTransformKind::Replace(content.replace("{{name}}", "demo"))
+        };
+        let force_binary = |path: &Path| path.extension().is_some_and(|ext| ext == "svg");
+        let options = CopyOptions {
+            callback: Some(&transform),
+            force_binary: Some(&force_binary),
+            ..Default::default()
+        };
+        copy_directory_with_options(&src, &dst, &options).unwrap();
+
+        assert_eq!(fs::read_to_string(dst.join("keep.svg")).unwrap(), "<svg>{{name}}</svg>");
+        assert_eq!(fs::read_to_string(dst.join("index.txt")).unwrap(), "demo");
+    }
+
+    /// Test that the executable bit survives the copy.
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_directory_preserves_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dst = dir.path().join("dst");
+        create_file(&src.join("gradlew"), "#!/bin/sh");
+        fs::set_permissions(src.join("gradlew"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        let transform = |content: &str, _: &Path| FileTransformKind::Replace(content.to_string());
+        copy_directory_with_options(
+            &src,
+            &dst,
+            &CopyOptions { callback: Some(&transform), ..Default::default() },
+        )
+        .unwrap();
+
+        let mode = fs::metadata(dst.join("gradlew")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    /// Test that symlinks are recreated as symlinks and not followed.
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_directory_recreates_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dst = dir.path().join("dst");
+        create_file(&src.join("real/config.json"), "{}");
+        symlink("real/config.json", src.join("config.json")).unwrap();
+        symlink("real", src.join("linked")).unwrap();
+        symlink(src.join("real/config.json"), src.join("absolute.json")).unwrap();
+
+        copy_directory_with_options(
+            &src,
+            &dst,
+            &CopyOptions { callback: None, ..Default::default() },
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_link(dst.join("config.json")).unwrap(), Path::new("real/config.json"));
+        assert_eq!(fs::read_link(dst.join("linked")).unwrap(), Path::new("real"));
+        assert_eq!(
+            fs::read_link(dst.join("absolute.json")).unwrap(),
+            Path::new("real/config.json")
+        );
+        assert_eq!(fs::read_to_string(dst.join("linked/config.json")).unwrap(), "{}");
+        assert_eq!(pre_count_files(&src, None).unwrap(), 4);
+    }
+
+    /// Test that symlinks escaping the template are rejected.
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_directory_rejects_symlink_outside_template() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        create_file(&dir.path().join("secret.txt"), "secret");
+        fs::create_dir_all(&src).unwrap();
+        symlink("../secret.txt", src.join("secret.txt")).unwrap();
+
+        let result =
+            copy_directory_with_options(&src, &dir.path().join("dst"), &CopyOptions::default());
+
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("points outside the template"));
+    }
+
+    /// Test that symlinks reaching outside the template through other symlinks are rejected.
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_directory_rejects_symlink_chain_outside_template() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("nested")).unwrap();
+        symlink(".", src.join("nested/here")).unwrap();
+        symlink("nested/here/here/../..", src.join("escape")).unwrap();
+
+        let result =
+            copy_directory_with_options(&src, &dir.path().join("dst"), &CopyOptions::default());
+
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.contains("points outside the template"));
+    }
+
+    /// Test that empty directories are kept unless filtered out.
+    #[test]
+    fn test_copy_directory_keeps_empty_directories() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dst = dir.path().join("dst");
+        fs::create_dir_all(src.join("logs")).unwrap();
+        fs::create_dir_all(src.join("assets/icons")).unwrap();
+        fs::create_dir_all(src.join("node_modules/.cache")).unwrap();
+
+        let filter = PathFilter::new(&[], &["/node_modules".to_string()]).unwrap();
+        let options = CopyOptions { filter: Some(&filter), ..Default::default() };
+        copy_directory_with_options(&src, &dst, &options).unwrap();
+
+        assert!(dst.join("logs").is_dir());
+        assert!(dst.join("assets/icons").is_dir());
+        assert!(!dst.join("node_modules").exists());
+    }
+
+    /// Test that `copy_directory_with_options` renders file and directory names.
+    #[test]
+    fn test_copy_directory_renders_names() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dst = dir.path().join("dst");
+        create_file(&src.join("src/{{name}}/lib.rs"), "lib");
+        create_file(&src.join("__name__.config.ts"), "config");
+        fs::create_dir_all(src.join("{{name}}_empty")).unwrap();
+
+        let render = |name: &str| Ok(name.replace("{{name}}", "demo").replace("__name__", "demo"));
+        let options = CopyOptions { render_name: Some(&render), ..Default::default() };
+        copy_directory_with_options(&src, &dst, &options).unwrap();
+
+        assert_eq!(fs::read_to_string(dst.join("src/demo/lib.rs")).unwrap(), "lib");
+        assert_eq!(fs::read_to_string(dst.join("demo.config.ts")).unwrap(), "config");
+        assert!(dst.join("demo_empty").is_dir());
+    }
+
+    /// Test that `copy_directory_with_options` reports names rendering to the same target.
+    #[test]
+    fn test_copy_directory_reports_name_collisions() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        create_file(&src.join("a/{{name}}.ts"), "rendered");
+        create_file(&src.join("a/demo.ts"), "plain");
+
+        let render = |name: &str| Ok(name.replace("{{name}}", "demo"));
+        let options = CopyOptions { render_name: Some(&render), ..Default::default() };
+        let err = copy_directory_with_options(&src, &dir.path().join("dst"), &options).unwrap_err();
+
+        assert!(err.to_string().contains("Name collision"), "{}", err);
+        assert!(err.to_string().ends_with("dst/a/demo.ts'"), "{}", err);
+    }
+
+    /// Test that `copy_directory_with_options` rejects names leaving the target directory.
+    #[test]
+    fn test_copy_directory_rejects_names_leaving_the_target() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        create_file(&src.join("{{name}}.txt"), "x");
+
+        for value in ["../escape", "/abs", ""] {
+            let render = |name: &str| {
+                Ok(if value.is_empty() { String::new() } else { name.replace("{{name}}", value) })
+            };
+            let options = CopyOptions { render_name: Some(&render), ..Default::default() };
+            let err =
+                copy_directory_with_options(&src, &dir.path().join("dst"), &options).unwrap_err();
+            assert!(err.to_string().contains("renders to the invalid name"), "{}", err);
+        }
+        assert!(!dir.path().join("escape.txt").exists());
+    }
 }
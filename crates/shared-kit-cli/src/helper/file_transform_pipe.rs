@@ -3,9 +3,17 @@ use std::{
     sync::Arc,
 };
 
+use anyhow::Context;
 use indicatif::ProgressBar;
 
-use crate::helper::file_system::FileTransformKind;
+use crate::{
+    constant::{TemplateVar, TemplateVarValues},
+    helper::{
+        file_system::FileTransformKind,
+        path_matcher::PathFilter,
+        template_engine::{self, Value},
+    },
+};
 
 pub type TransformContext = (String, PathBuf);
 pub type TransformNext = Arc<dyn Fn(TransformContext) -> FileTransformKind + Send + Sync>;
@@ -48,7 +56,7 @@ impl FileTransformPipe {
         self,
         final_handler: impl Fn(TransformContext) -> FileTransformKind + Send + Sync + 'static,
     ) -> impl Fn(&str, &Path) -> FileTransformKind + Send + Sync + 'static {
-        let mut next: TransformNext = Arc::new(move |ctx| final_handler(ctx));
+        let mut next: TransformNext = Arc::new(final_handler);
 
         for middleware in self.middlewares.into_iter().rev() {
             let curr = middleware.clone();
@@ -68,3 +76,312 @@ pub fn copy_file_progress_middleware(pb: Arc<ProgressBar>, origin: PathBuf) -> M
         next((_content, path))
     })
 }
+
+pub fn filter_files_middleware(filter: Arc<PathFilter>, origin: PathBuf) -> Middleware {
+    make_middleware(move |(content, path), next| {
+        let relative_path = path.strip_prefix(&origin).unwrap_or(&path);
+        if !filter.is_file_allowed(relative_path) {
+            return FileTransformKind::Skip;
+        }
+        next((content, path))
+    })
+}
+
+/// Pairs each answered variable with its value and its `includes_paths` / `excludes_paths` scope.
+fn scoped_vars(
+    vars: Vec<TemplateVar>,
+    values: &TemplateVarValues,
+) -> anyhow::Result<Vec<(TemplateVar, String, PathFilter)>> {
+    vars.into_iter()
+        .filter_map(|var| values.get(var.name()).cloned().map(|value| (var, value)))
+        .map(|(var, value)| {
+            let scope =
+                PathFilter::new(&var.includes_paths, &var.excludes_paths).with_context(|| {
+                    format!("Invalid includes_paths/excludes_paths for '{}'", var.placeholder)
+                })?;
+            Ok((var, value, scope))
+        })
+        .collect()
+}
+
+/// Hands the changed content on and keeps it in the result of the remaining middlewares.
+fn next_with_content(
+    content: String,
+    changed: String,
+    path: PathBuf,
+    next: TransformNext,
+) -> FileTransformKind {
+    if changed == content {
+        return next((content, path));
+    }
+
+    match next((changed.clone(), path)) {
+        FileTransformKind::NoChange => FileTransformKind::Replace(changed),
+        FileTransformKind::Rename(new_name) => {
+            FileTransformKind::Overwrite { new_content: changed, new_name }
+        }
+        other => other,
+    }
+}
+
+pub fn replace_template_vars_middleware(
+    vars: Vec<TemplateVar>,
+    values: TemplateVarValues,
+    origin: PathBuf,
+) -> anyhow::Result<Middleware> {
+    let scoped_vars = scoped_vars(vars, &values)?;
+
+    Ok(make_middleware(move |(content, path), next| {
+        let relative_path = path.strip_prefix(&origin).unwrap_or(&path);
+
+        let mut replaced = content.clone();
+        for (var, value, scope) in &scoped_vars {
+            if scope.is_file_allowed(relative_path) {
+                replaced = replaced.replace(&var.placeholder, value);
+            }
+        }
+
+        next_with_content(content, replaced, path, next)
+    }))
+}
+
+/// Renders file contents with the [`template_engine`], before plain placeholder replacement.
+///
+/// A variable is only visible in the files allowed by its `includes_paths` / `excludes_paths`.
+/// Render failures become `FileTransformKind::Error` naming the file and line.
+pub fn render_template_middleware(
+    vars: Vec<TemplateVar>,
+    values: TemplateVarValues,
+    origin: PathBuf,
+) -> anyhow::Result<Middleware> {
+    let scoped_vars = scoped_vars(vars, &values)?;
+
+    Ok(make_middleware(move |(content, path), next| {
+        let relative_path = path.strip_prefix(&origin).unwrap_or(&path);
+
+        let context: template_engine::Context = scoped_vars
+            .iter()
+            .filter(|(_, _, scope)| scope.is_file_allowed(relative_path))
+            .map(|(var, value, _)| (var.name().to_string(), Value::Str(value.clone())))
+            .collect();
+
+        match template_engine::render(&content, &context) {
+            Ok(rendered) => next_with_content(content, rendered, path, next),
+            Err(err) => FileTransformKind::Error(format!(
+                "❌ Template error in {}:{}: {}",
+                relative_path.display(),
+                err.line,
+                err.message
+            )),
+        }
+    }))
+}
+
+/// Renders file and directory names with the same variables as the contents.
+///
+/// Names go through the [`template_engine`] and the plain placeholders, and `__name__` can be
+/// used for `{{name}}` where braces are inconvenient. `includes_paths` / `excludes_paths` only
+/// scope contents, every answered variable is available in names.
+pub fn template_name_renderer(
+    vars: Vec<TemplateVar>,
+    values: TemplateVarValues,
+) -> impl Fn(&str) -> anyhow::Result<String> + Send + Sync + 'static {
+    let answered: Vec<(TemplateVar, String)> = vars
+        .into_iter()
+        .filter_map(|var| values.get(var.name()).cloned().map(|value| (var, value)))
+        .collect();
+    let context: template_engine::Context = answered
+        .iter()
+        .map(|(var, value)| (var.name().to_string(), Value::Str(value.clone())))
+        .collect();
+
+    move |name| {
+        let mut rendered = template_engine::render(name, &context)
+            .map_err(|err| anyhow::anyhow!("{}", err.message))?;
+        for (var, value) in &answered {
+            rendered = rendered
+                .replace(&var.placeholder, value)
+                .replace(&format!("__{}__", var.name()), value);
+        }
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project_name_var() -> TemplateVar {
+        TemplateVar {
+            placeholder: "{{project_name}}".to_string(),
+            prompt: None,
+            default: Some("new_project".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn demo_values() -> TemplateVarValues {
+        TemplateVarValues::from([("project_name".to_string(), "demo".to_string())])
+    }
+
+    /// Test that `filter_files_middleware` skips excluded files.
+    #[test]
+    fn test_filter_files_middleware_skips_excluded() {
+        let filter = PathFilter::new(&[], &["/node_modules".to_string()]).unwrap();
+        let handler = FileTransformPipe::new()
+            .add(filter_files_middleware(Arc::new(filter), PathBuf::from("/tpl")))
+            .into_handler(|_| FileTransformKind::NoChange);
+
+        assert_eq!(handler("", Path::new("/tpl/node_modules/a.js")), FileTransformKind::Skip);
+        assert_eq!(handler("", Path::new("/tpl/src/a.js")), FileTransformKind::NoChange);
+    }
+
+    /// Test that `replace_template_vars_middleware` replaces placeholders in the content.
+    #[test]
+    fn test_replace_template_vars_middleware_replaces_content() {
+        let middleware =
+            replace_template_vars_middleware(vec![project_name_var()], demo_values(), "".into())
+                .unwrap();
+        let handler =
+            FileTransformPipe::new().add(middleware).into_handler(|_| FileTransformKind::NoChange);
+
+        let result = handler(r#"{ "name": "{{project_name}}" }"#, Path::new("package.json"));
+        assert_eq!(result, FileTransformKind::Replace(r#"{ "name": "demo" }"#.to_string()));
+    }
+
+    /// Test that `replace_template_vars_middleware` leaves content without placeholders unchanged.
+    #[test]
+    fn test_replace_template_vars_middleware_no_placeholder() {
+        let middleware =
+            replace_template_vars_middleware(vec![project_name_var()], demo_values(), "".into())
+                .unwrap();
+        let handler =
+            FileTransformPipe::new().add(middleware).into_handler(|_| FileTransformKind::NoChange);
+
+        assert_eq!(handler("plain text", Path::new("a.txt")), FileTransformKind::NoChange);
+    }
+
+    /// Test that `replace_template_vars_middleware` keeps a rename of a later middleware.
+    #[test]
+    fn test_replace_template_vars_middleware_keeps_rename() {
+        let middleware =
+            replace_template_vars_middleware(vec![project_name_var()], demo_values(), "".into())
+                .unwrap();
+        let handler = FileTransformPipe::new()
+            .add(middleware)
+            .into_handler(|_| FileTransformKind::Rename("b.txt".to_string()));
+
+        assert_eq!(
+            handler("{{project_name}}", Path::new("a.txt")),
+            FileTransformKind::Overwrite {
+                new_content: "demo".to_string(),
+                new_name: "b.txt".to_string()
+            }
+        );
+    }
+
+    /// Test that `replace_template_vars_middleware` only replaces inside the variable's paths.
+    #[test]
+    fn test_replace_template_vars_middleware_scoped_paths() {
+        let var = TemplateVar {
+            includes_paths: vec!["package.json".to_string(), "**/*.ts".to_string()],
+            excludes_paths: vec!["/vendor".to_string()],
+            ..project_name_var()
+        };
+        let middleware =
+            replace_template_vars_middleware(vec![var], demo_values(), PathBuf::from("/tpl"))
+                .unwrap();
+        let handler =
+            FileTransformPipe::new().add(middleware).into_handler(|_| FileTransformKind::NoChange);
+
+        assert_eq!(
+            handler("{{project_name}}", Path::new("/tpl/package.json")),
+            FileTransformKind::Replace("demo".to_string())
+        );
+        assert_eq!(
+            handler("{{project_name}}", Path::new("/tpl/src/index.ts")),
+            FileTransformKind::Replace("demo".to_string())
+        );
+        assert_eq!(
+            handler("{{project_name}}", Path::new("/tpl/vendor/lib.ts")),
+            FileTransformKind::NoChange
+        );
+        assert_eq!(
+            handler("{{project_name}}", Path::new("/tpl/README.md")),
+            FileTransformKind::NoChange
+        );
+    }
+
+    /// Test that `render_template_middleware` renders before the placeholders are replaced.
+    #[test]
+    fn test_render_template_middleware_renders_before_replace() {
+        let vars = vec![
+            project_name_var(),
+            TemplateVar { placeholder: "__use_ts__".to_string(), ..Default::default() },
+        ];
+        let values = TemplateVarValues::from([
+            ("project_name".to_string(), "My App".to_string()),
+            ("__use_ts__".to_string(), "false".to_string()),
+        ]);
+        let handler = FileTransformPipe::new()
+            .add(render_template_middleware(vars.clone(), values.clone(), "".into()).unwrap())
+            .add(replace_template_vars_middleware(vars, values, "".into()).unwrap())
+            .into_handler(|_| FileTransformKind::NoChange);
+
+        let content = "{{ project_name | kebab_case }} {{project_name}} __use_ts__";
+        assert_eq!(
+            handler(content, Path::new("a.txt")),
+            FileTransformKind::Replace("my-app My App false".to_string())
+        );
+        assert_eq!(handler("plain text", Path::new("a.txt")), FileTransformKind::NoChange);
+    }
+
+    /// Test that `render_template_middleware` reports the file and line of template errors.
+    #[test]
+    fn test_render_template_middleware_reports_file_and_line() {
+        let middleware =
+            render_template_middleware(vec![project_name_var()], demo_values(), "/tpl".into())
+                .unwrap();
+        let handler =
+            FileTransformPipe::new().add(middleware).into_handler(|_| FileTransformKind::NoChange);
+
+        let result = handler("ok\n{% if project_name %}\n", Path::new("/tpl/src/index.ts"));
+        assert_eq!(
+            result,
+            FileTransformKind::Error(
+                "❌ Template error in src/index.ts:2: unclosed {% if %} block".to_string()
+            )
+        );
+    }
+
+    /// Test that `template_name_renderer` renders expressions and placeholders in names.
+    #[test]
+    fn test_template_name_renderer() {
+        let vars = vec![
+            project_name_var(),
+            TemplateVar { placeholder: "%PKG%".to_string(), ..Default::default() },
+        ];
+        let values = TemplateVarValues::from([
+            ("project_name".to_string(), "My App".to_string()),
+            ("%PKG%".to_string(), "com/example".to_string()),
+        ]);
+        let render = template_name_renderer(vars, values);
+
+        assert_eq!(render("{{ project_name | snake_case }}").unwrap(), "my_app");
+        assert_eq!(render("__project_name__.config.ts").unwrap(), "My App.config.ts");
+        assert_eq!(render("{{project_name}}-%PKG%").unwrap(), "My App-com/example");
+        assert_eq!(render("__init__.py").unwrap(), "__init__.py");
+        assert!(render("{% if project_name %}").is_err());
+    }
+
+    /// Test that `replace_template_vars_middleware` fails on invalid path patterns.
+    #[test]
+    fn test_replace_template_vars_middleware_invalid_scope() {
+        let var = TemplateVar {
+            excludes_paths: vec!["regex:(unclosed".to_string()],
+            ..project_name_var()
+        };
+        let result = replace_template_vars_middleware(vec![var], demo_values(), "".into());
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::{fs, path::Path, process::Command};
+
+use anyhow::Context;
+
+use crate::helper::repo::GitRef;
+
+/// Runs a git command and returns its output, failing with git's own error output.
+fn run_git(args: &[&str], cwd: Option<&Path>) -> anyhow::Result<String> {
+    let mut command = Command::new("git");
+    command.args(args).env("GIT_TERMINAL_PROMPT", "0");
+    if let Some(dir) = cwd {
+        command.current_dir(dir);
+    }
+
+    let output = command.output().context("Failed to run git, is it installed and on PATH?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "❌ git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Shallow clones `reference` of a remote into `dest`, strips the `.git` directory and returns
+/// the checked out commit.
+///
+/// Branches and tags are cloned with `--depth 1 --branch`. Commits are fetched on their own,
+/// servers that refuse fetching an unadvertised commit fall back to a full clone.
+pub fn clone_repo(clone_url: &str, reference: &GitRef, dest: &Path) -> anyhow::Result<String> {
+    let dest_str = dest.to_string_lossy();
+
+    match reference {
+        GitRef::Default => {
+            run_git(&["clone", "--quiet", "--depth", "1", "--", clone_url, &dest_str], None)?;
+        }
+        GitRef::Branch(name) | GitRef::Tag(name) => {
+            let args =
+                ["clone", "--quiet", "--depth", "1", "--branch", name, "--", clone_url, &dest_str];
+            run_git(&args, None)?;
+        }
+        GitRef::Commit(sha) => {
+            fs::create_dir_all(dest)
+                .with_context(|| format!("Failed to create clone dir: {}", dest.display()))?;
+            run_git(&["init", "--quiet"], Some(dest))?;
+            run_git(&["remote", "add", "origin", clone_url], Some(dest))?;
+
+            let shallow = run_git(&["fetch", "--quiet", "--depth", "1", "origin", sha], Some(dest));
+            if shallow.is_err() {
+                run_git(&["fetch", "--quiet", "origin"], Some(dest))?;
+            }
+            run_git(&["checkout", "--quiet", sha], Some(dest))?;
+        }
+    }
+
+    let commit = run_git(&["rev-parse", "HEAD"], Some(dest))?;
+
+    let git_dir = dest.join(".git");
+    fs::remove_dir_all(&git_dir)
+        .with_context(|| format!("Failed to remove git metadata: {}", git_dir.display()))?;
+    Ok(commit)
+}
+
+/// Reads a value of the user's git config, e.g. `user.name`, `None` when unset or git is missing.
+pub fn config_value(key: &str) -> Option<String> {
+    run_git(&["config", "--get", key], None).ok().filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::{TempDir, tempdir};
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// A bare repo with `README.md` and `templates/react/` on `main` (tagged `v1`), then
+    /// `README.md` changed on `main` and `dev`.
+    ///
+    /// Returns the temp dir, the bare repo path and the commit of `v1`.
+    pub fn bare_repo() -> (TempDir, PathBuf, String) {
+        let temp = tempdir().unwrap();
+        let work = temp.path().join("work");
+        let bare = temp.path().join("template.git");
+        fs::create_dir_all(&work).unwrap();
+
+        git(&work, &["init", "--quiet", "--initial-branch", "main"]);
+        fs::write(work.join("README.md"), "v1").unwrap();
+        fs::create_dir_all(work.join("templates/react")).unwrap();
+        fs::write(work.join("templates/react/README.md"), "react").unwrap();
+        git(&work, &["add", "."]);
+        git(&work, &["commit", "--quiet", "-m", "v1"]);
+        git(&work, &["tag", "v1"]);
+        let first = git(&work, &["rev-parse", "HEAD"]);
+
+        fs::write(work.join("README.md"), "main").unwrap();
+        git(&work, &["commit", "--quiet", "-am", "main"]);
+        git(&work, &["checkout", "--quiet", "-b", "dev"]);
+        fs::write(work.join("README.md"), "dev").unwrap();
+        git(&work, &["commit", "--quiet", "-am", "dev"]);
+        git(&work, &["checkout", "--quiet", "main"]);
+
+        git(temp.path(), &["clone", "--quiet", "--bare", "work", "template.git"]);
+        (temp, bare, first)
+    }
+
+    fn clone_readme(url: &str, reference: GitRef) -> anyhow::Result<String> {
+        let dest = tempdir().unwrap();
+        let dir = dest.path().join("clone");
+        let commit = clone_repo(url, &reference, &dir)?;
+        assert_eq!(commit.len(), 40);
+        assert!(!dir.join(".git").exists());
+        Ok(fs::read_to_string(dir.join("README.md")).unwrap())
+    }
+
+    #[test]
+    fn test_clone_refs_from_bare_repo() {
+        let (_temp, bare, first) = bare_repo();
+        let url = format!("file://{}", bare.display());
+
+        assert_eq!(clone_readme(&url, GitRef::Default).unwrap(), "main");
+        assert_eq!(clone_readme(&url, GitRef::Branch("dev".into())).unwrap(), "dev");
+        assert_eq!(clone_readme(&url, GitRef::Tag("v1".into())).unwrap(), "v1");
+        assert_eq!(clone_readme(&url, GitRef::Commit(first)).unwrap(), "v1");
+    }
+
+    #[test]
+    fn test_clone_missing_branch_should_fail() {
+        let (_temp, bare, _) = bare_repo();
+        let url = format!("file://{}", bare.display());
+
+        let err = clone_readme(&url, GitRef::Branch("nope".into())).unwrap_err();
+
+        assert!(err.to_string().contains("git clone failed"), "{}", err);
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::Context;
+
+/// Keyword switching the working directory of the remaining steps to the generated project
+pub const CD_TARGET: &str = "CD_TARGET";
+
+/// Runs the `completed_script` steps of a template after it has been generated.
+///
+/// Steps run one after another through the system shell with their output streamed to the
+/// terminal. They start in the current directory until a `CD_TARGET` step switches to
+/// `target`. The first failing step stops the run.
+///
+/// # Arguments
+///
+/// * `steps` - Shell commands or keywords, e.g. `["CD_TARGET", "pnpm i"]`.
+/// * `target` - The generated project directory.
+pub fn run_completed_script(steps: &[String], target: &Path) -> anyhow::Result<()> {
+    let mut cwd: PathBuf = env::current_dir().context("Failed to get current directory")?;
+    let total = steps.len();
+
+    for (index, step) in steps.iter().enumerate() {
+        let step = step.trim();
+        let position = index + 1;
+
+        if step.is_empty() {
+            continue;
+        }
+
+        if step == CD_TARGET {
+            cwd = target.to_path_buf();
+            continue;
+        }
+
+        info_msg!("▶️ [{}/{}] Running '{}' in '{}'", position, total, step, cwd.display());
+
+        let status = shell_command(step)
+            .current_dir(&cwd)
+            .status()
+            .with_context(|| format!("Failed to start step {}/{}: '{}'", position, total, step))?;
+
+        if !status.success() {
+            let code = status.code().map_or_else(|| "signal".to_string(), |c| c.to_string());
+            anyhow::bail!(
+                "❌ Post-generation step {}/{} '{}' failed (exit code: {}). Remaining steps were skipped.",
+                position,
+                total,
+                step,
+                code
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn shell_command(step: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", step]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell_command(step: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", step]);
+    cmd
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn steps(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_run_completed_script_cd_target() {
+        let dir = tempdir().unwrap();
+
+        run_completed_script(&steps(&[CD_TARGET, "touch created.txt"]), dir.path()).unwrap();
+
+        assert!(dir.path().join("created.txt").exists());
+    }
+
+    #[test]
+    fn test_run_completed_script_stops_on_failure() {
+        let dir = tempdir().unwrap();
+
+        let result =
+            run_completed_script(&steps(&[CD_TARGET, "exit 3", "touch never.txt"]), dir.path());
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("step 2/3 'exit 3'"));
+        assert!(err.contains("exit code: 3"));
+        assert!(!dir.path().join("never.txt").exists());
+    }
+}
@@ -1,6 +1,14 @@
 #[macro_use]
 pub mod logger;
+pub mod cache;
+pub mod computed;
+pub mod config_edit;
+pub mod credentials;
 pub mod file_system;
 pub mod file_transform_pipe;
+pub mod git;
+pub mod hooks;
 pub mod path;
-pub mod repo;
\ No newline at end of file
+pub mod path_matcher;
+pub mod repo;
+pub mod template_engine;
@@ -1,6 +1,6 @@
 use dirs::home_dir;
 use path_clean::PathClean;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 pub fn expand_dir(path: &str) -> Option<PathBuf> {
     if let Some(stripped) = path.strip_prefix("~/") {
@@ -22,6 +22,25 @@ pub fn join_with_config_dir(config_path: Option<&PathBuf>, relative: &Path) -> P
     path.clean()
 }
 
+/// Resolves `path` to the location the OS would use, following every symlink on the way.
+///
+/// Unlike `fs::canonicalize` the path does not have to exist: the longest existing prefix is
+/// canonicalized and the rest is appended as text, it cannot contain symlinks.
+pub fn real_path(path: &Path) -> std::io::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let components: Vec<Component> = path.components().collect();
+
+    for end in (1..=components.len()).rev() {
+        let prefix: PathBuf = components[..end].iter().collect();
+        if let Ok(real) = std::fs::canonicalize(&prefix) {
+            let rest: PathBuf = components[end..].iter().collect();
+            return Ok(real.join(rest).clean());
+        }
+    }
+
+    Ok(path.clean())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,4 +104,24 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_real_path_follows_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::os::unix::fs::symlink(".", root.join("a/self")).unwrap();
+        std::os::unix::fs::symlink("a/b", root.join("to_b")).unwrap();
+
+        assert_eq!(
+            real_path(&root.join("to_b/missing/file")).unwrap(),
+            root.join("a/b/missing/file")
+        );
+        assert_eq!(real_path(&root.join("to_b/../..")).unwrap(), root);
+        assert_eq!(
+            real_path(&root.join("a/self/self/self/../..")).unwrap(),
+            root.parent().unwrap()
+        );
+    }
 }
@@ -0,0 +1,210 @@
+use std::path::{Component, Path};
+
+use anyhow::Context;
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+
+const REGEX_PREFIX: &str = "regex:";
+
+#[derive(Debug, Clone)]
+enum PathPattern {
+    /// A plain path relative to the template root, matches itself and everything below it
+    Plain(String),
+    /// A `regex:` prefixed pattern, matched against the whole relative path
+    Regex(Regex),
+    /// A glob pattern such as `**/*.ts`, matched against the whole relative path
+    Glob(GlobMatcher),
+}
+
+impl PathPattern {
+    fn parse(pattern: &str) -> anyhow::Result<Self> {
+        if let Some(expr) = pattern.strip_prefix(REGEX_PREFIX) {
+            let re = Regex::new(expr)
+                .with_context(|| format!("Invalid regex path pattern: '{}'", pattern))?;
+            return Ok(PathPattern::Regex(re));
+        }
+
+        let plain = pattern.trim().trim_start_matches("./").trim_matches('/');
+
+        if plain.contains(['*', '?', '[', '{']) {
+            let glob = GlobBuilder::new(plain)
+                .literal_separator(true)
+                .build()
+                .with_context(|| format!("Invalid glob path pattern: '{}'", pattern))?;
+            return Ok(PathPattern::Glob(glob.compile_matcher()));
+        }
+
+        Ok(PathPattern::Plain(plain.to_string()))
+    }
+
+    fn is_match(&self, relative: &str) -> bool {
+        match self {
+            PathPattern::Plain(plain) => {
+                relative == plain
+                    || relative
+                        .strip_prefix(plain.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            }
+            PathPattern::Regex(re) => re.is_match(relative),
+            PathPattern::Glob(glob) => glob.is_match(relative),
+        }
+    }
+}
+
+/// A set of path patterns: plain relative paths, globs or `regex:` prefixed expressions.
+#[derive(Debug, Clone, Default)]
+pub struct PathMatcher {
+    patterns: Vec<PathPattern>,
+}
+
+impl PathMatcher {
+    pub fn new(patterns: &[String]) -> anyhow::Result<Self> {
+        let patterns = patterns
+            .iter()
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| PathPattern::parse(p))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(PathMatcher { patterns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Checks whether a path relative to the template root matches any pattern.
+    pub fn is_match(&self, relative: &Path) -> bool {
+        let relative = normalize_relative(relative);
+        self.patterns.iter().any(|p| p.is_match(&relative))
+    }
+}
+
+/// An includes / excludes pair, used both for the template-level `includes` / `excludes`
+/// and for the per-variable `includes_paths` / `excludes_paths` scope.
+///
+/// Excludes always win. When includes are present, only matching files are kept.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    includes: PathMatcher,
+    excludes: PathMatcher,
+}
+
+impl PathFilter {
+    pub fn new(includes: &[String], excludes: &[String]) -> anyhow::Result<Self> {
+        Ok(PathFilter {
+            includes: PathMatcher::new(includes)?,
+            excludes: PathMatcher::new(excludes)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.includes.is_empty() && self.excludes.is_empty()
+    }
+
+    /// Whether a file at the given relative path should be kept.
+    ///
+    /// A file inside an excluded directory is excluded as well.
+    pub fn is_file_allowed(&self, relative: &Path) -> bool {
+        if relative.ancestors().any(|p| !p.as_os_str().is_empty() && self.excludes.is_match(p)) {
+            return false;
+        }
+
+        self.includes.is_empty() || self.includes.is_match(relative)
+    }
+
+    /// Whether a directory at the given relative path should be walked.
+    ///
+    /// Only excludes prune directories, includes may still match files deeper down.
+    pub fn is_dir_allowed(&self, relative: &Path) -> bool {
+        !self.excludes.is_match(relative)
+    }
+}
+
+/// Converts a relative path into a `/` separated string without leading `./`.
+fn normalize_relative(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_plain_pattern_matches_path_and_children() {
+        let matcher = PathMatcher::new(&patterns(&["/node_modules", "package.json"])).unwrap();
+
+        assert!(matcher.is_match(Path::new("node_modules")));
+        assert!(matcher.is_match(Path::new("node_modules/react/index.js")));
+        assert!(matcher.is_match(Path::new("package.json")));
+        assert!(!matcher.is_match(Path::new("node_modules_backup/a.js")));
+        assert!(!matcher.is_match(Path::new("src/package.json")));
+    }
+
+    #[test]
+    fn test_regex_pattern() {
+        let matcher = PathMatcher::new(&patterns(&["regex:^README(\\.md)?$"])).unwrap();
+
+        assert!(matcher.is_match(Path::new("README.md")));
+        assert!(matcher.is_match(Path::new("README")));
+        assert!(!matcher.is_match(Path::new("docs/README.md")));
+    }
+
+    #[test]
+    fn test_glob_pattern() {
+        let matcher = PathMatcher::new(&patterns(&["**/*.ts", "src/*.json"])).unwrap();
+
+        assert!(matcher.is_match(Path::new("index.ts")));
+        assert!(matcher.is_match(Path::new("src/deep/index.ts")));
+        assert!(matcher.is_match(Path::new("src/config.json")));
+        assert!(!matcher.is_match(Path::new("src/deep/config.json")));
+        assert!(!matcher.is_match(Path::new("index.js")));
+    }
+
+    #[test]
+    fn test_invalid_glob_should_fail() {
+        let result = PathMatcher::new(&patterns(&["src/[unclosed"]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_invalid_regex_should_fail() {
+        let result = PathMatcher::new(&patterns(&["regex:(unclosed"]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_filter_excludes_win_over_includes() {
+        let filter = PathFilter::new(&patterns(&["/src"]), &patterns(&["regex:\\.swp$"])).unwrap();
+
+        assert!(filter.is_file_allowed(Path::new("src/main.rs")));
+        assert!(!filter.is_file_allowed(Path::new("src/.main.rs.swp")));
+        assert!(!filter.is_file_allowed(Path::new("Cargo.toml")));
+        assert!(filter.is_dir_allowed(Path::new("docs")));
+    }
+
+    #[test]
+    fn test_filter_prunes_excluded_dirs() {
+        let filter = PathFilter::new(&[], &patterns(&["/target"])).unwrap();
+
+        assert!(!filter.is_dir_allowed(Path::new("target")));
+        assert!(filter.is_file_allowed(Path::new("src/lib.rs")));
+    }
+
+    #[test]
+    fn test_filter_excludes_files_below_excluded_dir() {
+        let filter = PathFilter::new(&[], &patterns(&["regex:^target$"])).unwrap();
+
+        assert!(!filter.is_file_allowed(Path::new("target/debug/app")));
+        assert!(filter.is_file_allowed(Path::new("targets.txt")));
+    }
+}
@@ -1,11 +1,30 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
 use anyhow::Context;
-use reqwest::blocking::Client;
+use indicatif::HumanDuration;
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{NoProxy, Proxy, StatusCode};
 use tempfile::TempDir;
 
 use crate::components::progress::download_file_with_progress;
+use crate::constant::{CacheSettings, Credential, ExtractLimits, NetworkSettings};
+use crate::helper::cache::{CacheMeta, RepoCache, key_component, now_secs};
+use crate::helper::credentials::{authorize, resolve_credential};
+use crate::helper::git::clone_repo;
+use crate::helper::path::real_path;
+
+/// Entries smaller than this are not checked against the compression ratio limit,
+/// small text files routinely compress far beyond any sensible ratio
+const RATIO_CHECK_MIN_SIZE: u64 = 1024 * 1024;
+
+/// Longest symlink target read from an archive, the `PATH_MAX` of common platforms
+const MAX_SYMLINK_TARGET_LEN: u64 = 4096;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GitRef {
@@ -20,67 +39,258 @@ pub enum RepoPlatform {
     GitHub,
     GitLab,
     Gitea,
+    Bitbucket,
+    Forgejo,
     Other(String),
 }
 
+/// Platform names accepted in the `[hosts]` config section
+const PLATFORM_NAMES: [&str; 5] = ["github", "gitlab", "gitea", "bitbucket", "forgejo"];
+
+impl RepoPlatform {
+    /// Parses a platform name from the `[hosts]` config section.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "github" => Some(RepoPlatform::GitHub),
+            "gitlab" => Some(RepoPlatform::GitLab),
+            "gitea" => Some(RepoPlatform::Gitea),
+            "bitbucket" => Some(RepoPlatform::Bitbucket),
+            "forgejo" => Some(RepoPlatform::Forgejo),
+            _ => None,
+        }
+    }
+
+    /// Picks the platform of a host (optionally with port), custom host mappings win over the
+    /// public hosts.
+    pub fn from_host(host: &str, hosts: &HashMap<String, String>) -> anyhow::Result<Self> {
+        if let Some(name) = hosts.get(host) {
+            return RepoPlatform::from_name(name).with_context(|| {
+                format!(
+                    "❌ Unknown platform '{}' for host '{}' in [hosts]. Expected one of: {}",
+                    name,
+                    host,
+                    PLATFORM_NAMES.join(", ")
+                )
+            });
+        }
+
+        Ok(match host {
+            "github.com" => RepoPlatform::GitHub,
+            "gitlab.com" => RepoPlatform::GitLab,
+            "bitbucket.org" => RepoPlatform::Bitbucket,
+            "codeberg.org" => RepoPlatform::Forgejo,
+            h if h.contains("gitea") => RepoPlatform::Gitea,
+            h => RepoPlatform::Other(h.to_string()),
+        })
+    }
+
+    /// The public host of the platform, used by the short `user/repo` form.
+    pub fn public_host(&self) -> Option<&'static str> {
+        match self {
+            RepoPlatform::GitHub => Some("github.com"),
+            RepoPlatform::GitLab => Some("gitlab.com"),
+            RepoPlatform::Gitea => Some("gitea.com"),
+            RepoPlatform::Bitbucket => Some("bitbucket.org"),
+            RepoPlatform::Forgejo => Some("codeberg.org"),
+            RepoPlatform::Other(_) => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            RepoPlatform::GitHub => "github",
+            RepoPlatform::GitLab => "gitlab",
+            RepoPlatform::Gitea => "gitea",
+            RepoPlatform::Bitbucket => "bitbucket",
+            RepoPlatform::Forgejo => "forgejo",
+            RepoPlatform::Other(host) => host,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RepoInfo {
     pub platform: RepoPlatform,
+    /// Scheme, host and port of the server, e.g. `https://git.example.com`
+    pub base_url: String,
     pub user: String,
     pub repo: String,
     pub r#ref: GitRef,
+    /// Remote of SSH and `file://` addresses, which are always fetched with `git clone`
+    pub git_url: Option<String>,
+    /// Directory inside the repository used as the template, e.g. `templates/react`
+    pub subdir: Option<String>,
+}
+
+/// How remote archives are fetched and extracted
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    pub limits: ExtractLimits,
+    pub cache: CacheSettings,
+    pub network: NetworkSettings,
+    /// Only use the download cache, never touch the network
+    pub offline: bool,
+    /// Custom host → platform mappings from the `[hosts]` config section
+    pub hosts: HashMap<String, String>,
+    /// Per-host credentials from the `[credentials]` config section
+    pub credentials: HashMap<String, Credential>,
+}
+
+enum FetchResult {
+    NotModified,
+    Downloaded { etag: Option<String>, last_modified: Option<String> },
+}
+
+/// Outcome of one download attempt, failures worth retrying are kept apart from final ones
+enum Attempt {
+    Done(FetchResult),
+    Retry(anyhow::Error),
 }
 
 pub struct ExtractedRepo {
     pub root_dir: PathBuf,
+    /// The commit the files were taken from, when the archive or clone reports it
+    pub commit: Option<String>,
+    /// SHA-256 of the downloaded archive, `None` for git clones
+    pub sha256: Option<String>,
     _tmp_dir: TempDir, // 保持生命周期，drop 时自动清理
 }
 
 impl RepoInfo {
-    pub fn download_url(&self) -> String {
+    /// Download cache key, `<platform>/<user>/<repo>/<ref>`.
+    ///
+    /// Self-hosted servers use their host instead of the platform name, so two instances of the
+    /// same platform never share entries.
+    pub fn cache_key(&self) -> String {
+        let host = self.host();
+        let platform = match self.platform.public_host() {
+            Some(public) if public == host => self.platform.name(),
+            _ => host,
+        };
         let reference = match &self.r#ref {
-            GitRef::Default => "main",
-            GitRef::Branch(b) => b,
-            GitRef::Tag(t) => t,
-            GitRef::Commit(c) => c,
+            GitRef::Default => "default".to_string(),
+            GitRef::Branch(b) => format!("branch-{}", b),
+            GitRef::Tag(t) => format!("tag-{}", t),
+            GitRef::Commit(c) => format!("commit-{}", c),
         };
 
-        match self.platform {
-            RepoPlatform::GitHub => {
+        [platform, &self.user, &self.repo, &reference].map(key_component).join("/")
+    }
+
+    pub fn download_url(&self) -> String {
+        let reference = self.reference();
+        let (base, user, repo) = (&self.base_url, &self.user, &self.repo);
+
+        match &self.platform {
+            RepoPlatform::GitHub => match &self.r#ref {
+                GitRef::Tag(_) => {
+                    format!("{}/{}/{}/archive/refs/tags/{}.zip", base, user, repo, reference)
+                }
+                GitRef::Commit(_) => {
+                    format!("{}/{}/{}/archive/{}.zip", base, user, repo, reference)
+                }
+                GitRef::Branch(_) | GitRef::Default => {
+                    format!("{}/{}/{}/archive/refs/heads/{}.zip", base, user, repo, reference)
+                }
+            },
+            RepoPlatform::GitLab => {
                 format!(
-                    "https://github.com/{}/{}/archive/refs/heads/{}.zip",
-                    self.user, self.repo, reference
+                    "{}/{}/{}/-/archive/{}/{}-{}.zip",
+                    base, user, repo, reference, repo, reference
                 )
             }
+            RepoPlatform::Gitea | RepoPlatform::Forgejo => {
+                format!("{}/{}/{}/archive/{}.zip", base, user, repo, reference)
+            }
+            RepoPlatform::Bitbucket => format!("{}/{}/{}/get/{}.zip", base, user, repo, reference),
+            // 其他平台没有zip下载地址，返回空串后改用 git clone
+            RepoPlatform::Other(_) => String::new(),
+        }
+    }
+
+    /// Archive URL of the GitHub REST API, which unlike the web archive URL accepts tokens.
+    pub fn github_api_download_url(&self) -> String {
+        format!("{}/zipball/{}", self.github_api_repo_url(), self.reference())
+    }
+
+    fn github_api_repo_url(&self) -> String {
+        let api = match self.host() {
+            "github.com" => "https://api.github.com".to_string(),
+            _ => format!("{}/api/v3", self.base_url),
+        };
+        format!("{}/repos/{}/{}", api, self.user, self.repo)
+    }
+
+    /// Platform API endpoint describing the repository, used to look up its default branch.
+    pub fn repo_api_url(&self) -> Option<String> {
+        let (base, user, repo) = (&self.base_url, &self.user, &self.repo);
+        match &self.platform {
+            RepoPlatform::GitHub => Some(self.github_api_repo_url()),
             RepoPlatform::GitLab => {
-                format!(
-                    "https://gitlab.com/{}/{}/-/archive/{}/{}-{}.zip",
-                    self.user, self.repo, reference, self.repo, reference
-                )
+                let namespace = user.replace('/', "%2F");
+                Some(format!("{}/api/v4/projects/{}%2F{}", base, namespace, repo))
             }
-            RepoPlatform::Gitea => {
-                format!("https://gitea.com/{}/{}/archive/{}.zip", self.user, self.repo, reference)
+            RepoPlatform::Gitea | RepoPlatform::Forgejo => {
+                Some(format!("{}/api/v1/repos/{}/{}", base, user, repo))
             }
-            RepoPlatform::Other(ref domain) => {
-                // 其他平台不支持直接下载zip，可以自定义处理或返回空串
-                warn_msg!("Warning: unsupported platform {}, fallback to empty url", domain);
-                String::new()
+            RepoPlatform::Bitbucket => {
+                let api = match self.host() {
+                    "bitbucket.org" => "https://api.bitbucket.org/2.0".to_string(),
+                    _ => format!("{}/api/2.0", base),
+                };
+                Some(format!("{}/repositories/{}/{}", api, user, repo))
             }
+            RepoPlatform::Other(_) => None,
+        }
+    }
+
+    /// The same repo with a `Default` ref replaced by the looked up default branch.
+    fn with_default_branch(&self, branch: String) -> RepoInfo {
+        let mut resolved = self.clone();
+        if resolved.r#ref == GitRef::Default {
+            resolved.r#ref = GitRef::Branch(branch);
         }
+        resolved
+    }
+
+    fn reference(&self) -> &str {
+        match &self.r#ref {
+            // Only used when the default branch could not be looked up
+            GitRef::Default => "main",
+            GitRef::Branch(b) => b,
+            GitRef::Tag(t) => t,
+            GitRef::Commit(c) => c,
+        }
+    }
+
+    /// The remote to `git clone` when there is no archive to download.
+    pub fn clone_url(&self) -> String {
+        match &self.git_url {
+            Some(url) => url.clone(),
+            None => format!("{}/{}/{}.git", self.base_url, self.user, self.repo),
+        }
+    }
+
+    /// Host and port of the server, without the scheme.
+    pub fn host(&self) -> &str {
+        self.base_url.split_once("://").map_or(self.base_url.as_str(), |(_, host)| host)
     }
 }
 
-pub fn parse_repo_input(input: &String) -> anyhow::Result<RepoInfo> {
+/// Parses a repository address, resolving URL hosts through the custom `hosts` mappings.
+pub fn parse_repo_input(input: &str, hosts: &HashMap<String, String>) -> anyhow::Result<RepoInfo> {
     // Try to parse URL form
     if input.starts_with("http://") || input.starts_with("https://") {
-        parse_from_url(input)
+        parse_from_url(input, hosts)
+    } else if is_git_remote(input) {
+        parse_from_git_remote(input, hosts)
     } else {
         parse_from_short(input)
     }
 }
 
-pub fn parse_from_url(input: &String) -> anyhow::Result<RepoInfo> {
-    let raw: &str = input.as_str(); // or &input[..]
+pub fn parse_from_url(input: &str, hosts: &HashMap<String, String>) -> anyhow::Result<RepoInfo> {
+    let raw: &str = input;
     let mut base = raw;
     let mut suffix: Option<(&str, &str)> = None;
 
@@ -94,21 +304,31 @@ pub fn parse_from_url(input: &String) -> anyhow::Result<RepoInfo> {
 
     let url = url::Url::parse(base)?;
     let host = url.host_str().ok_or_else(|| anyhow::anyhow!("Invalid URL: {}", raw))?;
-    let segments: Vec<_> = url.path_segments().map_or(Vec::new(), |s| s.collect());
-
-    if segments.len() < 2 {
-        anyhow::bail!("URL path must contain user and repo: {}", url);
-    }
+    let segments: Vec<_> =
+        url.path_segments().map_or(Vec::new(), |s| s.filter(|s| !s.is_empty()).collect());
 
-    let user = segments[0].to_string();
-    let repo = segments[1].trim_end_matches(".git").to_string();
+    let host_with_port = url.port().map(|port| format!("{}:{}", host, port));
+    let platform = match host_with_port {
+        Some(authority) if hosts.contains_key(&authority) => {
+            RepoPlatform::from_host(&authority, hosts)?
+        }
+        _ => RepoPlatform::from_host(host, hosts)?,
+    };
 
-    let platform = match host {
-        "github.com" => RepoPlatform::GitHub,
-        "gitlab.com" => RepoPlatform::GitLab,
-        h if h.contains("gitea") => RepoPlatform::Gitea,
-        h => RepoPlatform::Other(h.to_string()),
+    // GitLab projects can sit in nested groups, there the subdirectory follows a `/-/` segment
+    let (path, subdir) = match (&platform, segments.iter().position(|s| *s == "-")) {
+        (RepoPlatform::GitLab, Some(pos)) => (&segments[..pos], join_subdir(&segments[pos + 1..])),
+        (RepoPlatform::GitLab, None) => (&segments[..], None),
+        _ => (&segments[..segments.len().min(2)], join_subdir(segments.get(2..).unwrap_or(&[]))),
+    };
+    let Some((repo, namespace)) = path.split_last().filter(|(_, namespace)| !namespace.is_empty())
+    else {
+        anyhow::bail!("URL path must contain user and repo: {}", url);
     };
+    let user = namespace.join("/");
+    let repo = repo.trim_end_matches(".git").to_string();
+
+    let base_url = url.origin().ascii_serialization();
 
     let r#ref = match suffix {
         Some(("#", val)) => GitRef::Branch(val.to_string()),
@@ -122,16 +342,97 @@ pub fn parse_from_url(input: &String) -> anyhow::Result<RepoInfo> {
         _ => GitRef::Default,
     };
 
-    Ok(RepoInfo { platform, user, repo, r#ref })
+    Ok(RepoInfo { platform, base_url, user, repo, r#ref, git_url: None, subdir })
+}
+
+/// Joins the path segments after `user/repo`, `None` when there are none.
+fn join_subdir(segments: &[&str]) -> Option<String> {
+    let parts: Vec<_> = segments.iter().filter(|s| !s.is_empty()).copied().collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// `ssh://`, `git://` and `file://` URLs, and the scp-like `git@host:user/repo.git` form.
+fn is_git_remote(input: &str) -> bool {
+    let scp_like = regex::Regex::new(r"^[\w.-]+@[\w.-]+:[^/]").unwrap();
+    ["ssh://", "git://", "file://"].iter().any(|scheme| input.starts_with(scheme))
+        || scp_like.is_match(input)
+}
+
+/// Parses an address that can only be fetched with `git clone`.
+///
+/// A `#branch` or `@tag` / `@commit` suffix after the last path segment picks the ref, like
+/// in the other forms.
+pub fn parse_from_git_remote(
+    input: &str,
+    hosts: &HashMap<String, String>,
+) -> anyhow::Result<RepoInfo> {
+    let (mut remote, mut r#ref) = match input.split_once('#') {
+        Some((remote, branch)) => (remote, GitRef::Branch(branch.to_string())),
+        None => (input, GitRef::Default),
+    };
+    let last_segment = remote.rfind(['/', ':']).map_or(0, |pos| pos + 1);
+    if let Some(pos) = remote[last_segment..].find('@') {
+        let val = &remote[last_segment + pos + 1..];
+        r#ref = if is_probable_commit(val) {
+            GitRef::Commit(val.to_string())
+        } else {
+            GitRef::Tag(val.to_string())
+        };
+        remote = &remote[..last_segment + pos];
+    }
+
+    let (host, path) = if remote.contains("://") {
+        let url = url::Url::parse(remote).with_context(|| format!("Invalid URL: {}", input))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => String::new(),
+        };
+        (host, url.path().to_string())
+    } else {
+        // git@host:user/repo.git
+        let (authority, path) = remote.split_once(':').unwrap_or((remote, ""));
+        let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        (host.to_string(), path.to_string())
+    };
+
+    let segments: Vec<_> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let Some((repo, parents)) = segments.split_last().filter(|(_, parents)| !parents.is_empty())
+    else {
+        anyhow::bail!("Git remote path must contain user and repo: {}", input);
+    };
+
+    let platform = match host.as_str() {
+        "" => RepoPlatform::Other("local".to_string()),
+        host => RepoPlatform::from_host(host, hosts)?,
+    };
+    let base_url = match host.as_str() {
+        "" => "file://".to_string(),
+        host => format!("https://{}", host),
+    };
+
+    Ok(RepoInfo {
+        platform,
+        base_url,
+        user: parents.join("/"),
+        repo: repo.trim_end_matches(".git").to_string(),
+        r#ref,
+        git_url: Some(remote.to_string()),
+        subdir: None,
+    })
 }
 
-pub fn parse_from_short(input: &String) -> anyhow::Result<RepoInfo> {
-    let re = regex::Regex::new(r"^(?P<user>[^/\s]+)/(?P<repo>[^\s@#]+)([@#](?P<ref>[^\s]+))?$")?;
+pub fn parse_from_short(input: &str) -> anyhow::Result<RepoInfo> {
+    let re = regex::Regex::new(
+        r"^(?P<user>[^/\s]+)/(?P<repo>[^/\s@#]+)(/(?P<subdir>[^\s@#]*))?([@#](?P<ref>[^\s]+))?$",
+    )?;
     let caps =
         re.captures(input).with_context(|| format!("Invalid short repo format: '{}'", input))?;
 
     let user = caps["user"].to_string();
     let repo = caps["repo"].to_string();
+    let subdir =
+        caps.name("subdir").and_then(|m| join_subdir(&m.as_str().split('/').collect::<Vec<_>>()));
 
     let r#ref = match caps.name("ref") {
         Some(m) => {
@@ -146,7 +447,15 @@ pub fn parse_from_short(input: &String) -> anyhow::Result<RepoInfo> {
         None => GitRef::Default,
     };
 
-    Ok(RepoInfo { platform: RepoPlatform::GitHub, user, repo, r#ref })
+    Ok(RepoInfo {
+        platform: RepoPlatform::GitHub,
+        base_url: "https://github.com".to_string(),
+        user,
+        repo,
+        r#ref,
+        git_url: None,
+        subdir,
+    })
 }
 
 fn is_probable_commit(s: &str) -> bool {
@@ -164,49 +473,615 @@ fn find_root_dir(extract_dir: &Path) -> anyhow::Result<PathBuf> {
     anyhow::bail!("No extracted directory found in zip")
 }
 
-fn extract_zip(zip_path: &Path, extract_dir: &Path) -> anyhow::Result<()> {
+/// Extracts a zip archive entry by entry, enforcing the given limits.
+///
+/// Entries escaping `extract_dir`, either by their name or as symlinks, are rejected, as are
+/// entries placed below a symlink of the archive. Sizes are checked against the bytes actually
+/// written while they are written, not only the sizes declared in the archive.
+fn extract_zip(zip_path: &Path, extract_dir: &Path, limits: &ExtractLimits) -> anyhow::Result<()> {
     std::fs::create_dir_all(extract_dir).context("Failed to create extract dir")?;
+    let extract_root = fs::canonicalize(extract_dir).context("Failed to resolve extract dir")?;
     let zip_file = std::fs::File::open(zip_path).context("Failed to open zip file")?;
     let mut archive = zip::ZipArchive::new(zip_file).context("Failed to read zip archive")?;
-    archive.extract(extract_dir).context("Failed to extract zip archive")
+
+    if archive.len() > limits.max_files {
+        anyhow::bail!(
+            "❌ Zip archive has {} entries, more than the allowed {} (extract.max_files)",
+            archive.len(),
+            limits.max_files
+        );
+    }
+
+    let symlinks = archive_symlinks(&mut archive)?;
+    let mut extracted_links = Vec::new();
+    let mut total_size: u64 = 0;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).context("Failed to read zip entry")?;
+        let name = entry.name().to_string();
+        let relative = entry
+            .enclosed_name()
+            .with_context(|| format!("❌ Zip entry '{}' escapes the extract directory", name))?;
+
+        // Writing below a symlink would follow it, wherever it points
+        let is_symlink = entry.is_symlink();
+        if let Some(link) = relative
+            .ancestors()
+            .filter(|ancestor| !is_symlink || *ancestor != relative)
+            .find(|ancestor| symlinks.contains(*ancestor))
+        {
+            anyhow::bail!(
+                "❌ Zip entry '{}' is placed below the symlink '{}' of the archive",
+                name,
+                link.display()
+            );
+        }
+
+        let out_path = extract_dir.join(&relative);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path)
+                .with_context(|| format!("Failed to create dir: {}", out_path.display()))?;
+            continue;
+        }
+
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+        }
+
+        if is_symlink {
+            let mut target = Vec::new();
+            (&mut entry)
+                .take(MAX_SYMLINK_TARGET_LEN + 1)
+                .read_to_end(&mut target)
+                .with_context(|| format!("Failed to read symlink entry '{}'", name))?;
+            if target.len() as u64 > MAX_SYMLINK_TARGET_LEN {
+                anyhow::bail!(
+                    "❌ Zip entry '{}' is a symlink with a target longer than {} bytes",
+                    name,
+                    MAX_SYMLINK_TARGET_LEN
+                );
+            }
+            total_size += target.len() as u64;
+            if total_size > limits.max_total_size {
+                anyhow::bail!(
+                    "❌ Zip archive expands beyond the allowed {} bytes (extract.max_total_size)",
+                    limits.max_total_size
+                );
+            }
+            let link = String::from_utf8(target)
+                .with_context(|| format!("Failed to read symlink entry '{}'", name))?;
+            extract_symlink(&extract_root, &out_path, &name, &link)?;
+            extracted_links.push((name, out_path, link));
+            continue;
+        }
+
+        let compressed = entry.compressed_size().max(1);
+        let ratio_limit =
+            compressed.saturating_mul(limits.max_compression_ratio).max(RATIO_CHECK_MIN_SIZE);
+        let remaining = limits.max_total_size - total_size;
+        let mut out_file = fs::File::create(&out_path)
+            .with_context(|| format!("Failed to create file: {}", out_path.display()))?;
+        let written =
+            io::copy(&mut (&mut entry).take(remaining.min(ratio_limit) + 1), &mut out_file)
+                .with_context(|| format!("Failed to extract zip entry '{}'", name))?;
+
+        total_size += written;
+        if total_size > limits.max_total_size {
+            anyhow::bail!(
+                "❌ Zip archive expands beyond the allowed {} bytes (extract.max_total_size)",
+                limits.max_total_size
+            );
+        }
+
+        if written > ratio_limit {
+            anyhow::bail!(
+                "❌ Zip entry '{}' has a compression ratio above the allowed {} (extract.max_compression_ratio)",
+                name,
+                limits.max_compression_ratio
+            );
+        }
+
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&out_path, fs::Permissions::from_mode(mode & 0o777))
+                .with_context(|| format!("Failed to set permissions: {}", out_path.display()))?;
+        }
+    }
+
+    // A symlink may point through symlinks extracted after it, check where it really ends up
+    for (name, out_path, link) in extracted_links {
+        check_symlink_inside(&extract_root, &out_path, &name, &link)?;
+    }
+
+    Ok(())
+}
+
+/// Collects the paths of all symlink entries without decompressing anything.
+fn archive_symlinks(archive: &mut zip::ZipArchive<fs::File>) -> anyhow::Result<HashSet<PathBuf>> {
+    let mut symlinks = HashSet::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).context("Failed to read zip entry")?;
+        if let (true, Some(path)) = (entry.is_symlink(), entry.enclosed_name()) {
+            symlinks.insert(path);
+        }
+    }
+    Ok(symlinks)
+}
+
+fn extract_symlink(
+    extract_root: &Path,
+    out_path: &Path,
+    name: &str,
+    link: &str,
+) -> anyhow::Result<()> {
+    if Path::new(link).is_absolute() {
+        anyhow::bail!(
+            "❌ Zip entry '{}' is a symlink escaping the extract directory: '{}'",
+            name,
+            link
+        );
+    }
+    let parent = out_path.parent().unwrap_or(extract_root);
+    check_symlink_inside(extract_root, &parent.join(link), name, link)?;
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(link, out_path)
+        .with_context(|| format!("Failed to create symlink: {}", out_path.display()))?;
+
+    #[cfg(not(unix))]
+    fs::write(out_path, link.as_bytes())
+        .with_context(|| format!("Failed to create file: {}", out_path.display()))?;
+
+    Ok(())
 }
 
-fn download_zip_to_path(url: &str, dest_path: &Path) -> anyhow::Result<()> {
-    let client = Client::new();
-    let resp =
-        client.get(url).send().with_context(|| format!("Failed to send GET request to {}", url))?;
+fn check_symlink_inside(
+    extract_root: &Path,
+    path: &Path,
+    name: &str,
+    link: &str,
+) -> anyhow::Result<()> {
+    let resolved =
+        real_path(path).with_context(|| format!("Failed to resolve symlink entry '{}'", name))?;
+    if !resolved.starts_with(extract_root) {
+        anyhow::bail!(
+            "❌ Zip entry '{}' is a symlink escaping the extract directory: '{}'",
+            name,
+            link
+        );
+    }
+    Ok(())
+}
+
+/// HTTP client for archive downloads and API lookups, some APIs reject requests without a
+/// `User-Agent`.
+///
+/// Without a configured proxy the `HTTPS_PROXY` / `HTTP_PROXY` / `ALL_PROXY` and `NO_PROXY` env
+/// vars apply.
+fn http_client(network: &NetworkSettings) -> anyhow::Result<Client> {
+    let mut builder = Client::builder()
+        .user_agent(concat!("shared-kit/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(Duration::from_secs(network.connect_timeout))
+        .timeout(Duration::from_secs(network.timeout));
 
+    if let Some(proxy) = &network.proxy {
+        let no_proxy = match &network.no_proxy {
+            Some(hosts) => NoProxy::from_string(hosts),
+            None => NoProxy::from_env(),
+        };
+        // The URL may contain a password, keep it out of the error
+        let proxy = Proxy::all(proxy).context("❌ Invalid proxy URL in [network]")?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy));
+    }
+
+    builder.build().context("Failed to create HTTP client")
+}
+
+/// Asks the platform API for the default branch of a repo.
+fn lookup_default_branch(
+    repo_info: &RepoInfo,
+    credential: Option<&Credential>,
+    network: &NetworkSettings,
+) -> anyhow::Result<String> {
+    let url = repo_info.repo_api_url().context("The platform has no repository API")?;
+    let mut request = http_client(network)?.get(&url);
+    if let Some(credential) = credential {
+        request = authorize(request, &repo_info.platform, credential);
+    }
+
+    let resp = request.send().with_context(|| format!("Failed to send GET request to {}", url))?;
     if !resp.status().is_success() {
-        anyhow::bail!("Failed to download repo zip: HTTP {}", resp.status());
+        anyhow::bail!("HTTP {}", resp.status());
     }
 
-    download_file_with_progress(resp, dest_path)
+    let body = resp.text().context("Failed to read repository API response")?;
+    let body: serde_json::Value =
+        serde_json::from_str(&body).context("Invalid repository API response")?;
+    let branch = match repo_info.platform {
+        RepoPlatform::Bitbucket => body.pointer("/mainbranch/name"),
+        _ => body.get("default_branch"),
+    };
+    branch
+        .and_then(|b| b.as_str())
+        .map(String::from)
+        .context("Repository API response has no default branch")
 }
 
-fn download_and_extract_zip(download_url: &str) -> anyhow::Result<ExtractedRepo> {
-    let tmp_dir = tempfile::tempdir().context("Failed to create temp dir")?;
-    let zip_path = tmp_dir.path().join("repo.zip");
+/// Picks the archive URL of a `Default` ref.
+///
+/// A fresh (or, offline, any) cached archive keeps the URL it was downloaded from, so the
+/// platform API is only asked when the archive has to be fetched again. A failed lookup falls
+/// back to `main`.
+fn default_branch_archive_url(
+    cache: &RepoCache,
+    repo_info: &RepoInfo,
+    credential: Option<&Credential>,
+    options: &FetchOptions,
+) -> String {
+    let cached = cache.entry(&repo_info.cache_key()).read_meta();
+    if let Some(meta) = cached.filter(|meta| options.offline || meta.is_fresh(options.cache.ttl)) {
+        return meta.url;
+    }
+    if options.offline {
+        return archive_url(repo_info, credential);
+    }
 
-    download_zip_to_path(download_url, &zip_path)?;
+    match lookup_default_branch(repo_info, credential, &options.network) {
+        Ok(branch) => {
+            info_msg!("🌿 Default branch of {}/{}: {}", repo_info.user, repo_info.repo, branch);
+            archive_url(&repo_info.with_default_branch(branch), credential)
+        }
+        Err(err) => {
+            warn_msg!(
+                "Failed to look up the default branch of {}/{}, trying 'main': {:#}",
+                repo_info.user,
+                repo_info.repo,
+                err
+            );
+            archive_url(repo_info, credential)
+        }
+    }
+}
+
+/// Hex encoded SHA-256 of a file.
+fn file_sha256(path: &Path) -> anyhow::Result<String> {
+    let mut file = fs::File::open(path)
+        .with_context(|| format!("Failed to open archive: {}", path.display()))?;
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut buffer = [0; 8192];
+    loop {
+        let n = file.read(&mut buffer).context("Failed to read archive")?;
+        if n == 0 {
+            break;
+        }
+        context.update(&buffer[..n]);
+    }
+    Ok(context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Fails when an archive does not match its pinned checksum, `expected` may be prefixed with
+/// `sha256:`.
+fn verify_checksum(name: &str, expected: &str, actual: &str) -> anyhow::Result<()> {
+    let expected = expected.trim();
+    let normalized = expected.strip_prefix("sha256:").unwrap_or(expected).to_lowercase();
+    if normalized != actual {
+        anyhow::bail!(
+            "❌ Checksum mismatch for '{}'\n  expected: {}\n  actual:   {}\nThe archive changed since it was pinned. Check the source, and run `shared-kit template pin` again if the change is expected.",
+            name,
+            normalized,
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// Reads the commit id `git archive` stores as the zip comment.
+fn archive_commit(zip_path: &Path) -> Option<String> {
+    let file = fs::File::open(zip_path).ok()?;
+    let archive = zip::ZipArchive::new(file).ok()?;
+    let comment = String::from_utf8_lossy(archive.comment()).trim().to_string();
+    is_probable_commit(&comment).then_some(comment)
+}
+
+/// Downloads an archive, sending the cached validators so the server can answer `304`.
+///
+/// Connection errors, timeouts, `429` / `5xx` answers and broken downloads are retried with
+/// exponential backoff.
+fn download_zip_to_path(
+    url: &str,
+    dest_path: &Path,
+    cached: Option<&CacheMeta>,
+    auth: Option<(&RepoPlatform, &Credential)>,
+    network: &NetworkSettings,
+) -> anyhow::Result<FetchResult> {
+    let client = http_client(network)?;
+    let mut delay = Duration::from_millis(network.retry_delay);
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+        let mut request = client.get(url);
+        if let Some((platform, credential)) = auth {
+            request = authorize(request, platform, credential);
+        }
+        if let Some(meta) = cached {
+            if let Some(etag) = &meta.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &meta.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        match try_download(request, url, dest_path, auth.is_some())? {
+            Attempt::Done(result) => return Ok(result),
+            Attempt::Retry(err) if attempt <= network.retries => {
+                warn_msg!(
+                    "Download failed, retrying in {} ({}/{}): {:#}",
+                    HumanDuration(delay),
+                    attempt,
+                    network.retries,
+                    err
+                );
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            Attempt::Retry(err) => {
+                return Err(err.context(format!("❌ Download failed after {} attempt(s)", attempt)));
+            }
+        }
+    }
+}
+
+fn try_download(
+    request: RequestBuilder,
+    url: &str,
+    dest_path: &Path,
+    authorized: bool,
+) -> anyhow::Result<Attempt> {
+    let resp = match request.send() {
+        Ok(resp) => resp,
+        Err(err) => {
+            let err =
+                anyhow::Error::new(err).context(format!("Failed to send GET request to {}", url));
+            return Ok(Attempt::Retry(err));
+        }
+    };
+
+    let status = resp.status();
+    if status == StatusCode::NOT_MODIFIED {
+        return Ok(Attempt::Done(FetchResult::NotModified));
+    }
+    if status == StatusCode::NOT_FOUND && !authorized {
+        anyhow::bail!(
+            "Failed to download repo zip: HTTP {}. Private repositories need a token, set it in the platform env var (e.g. GITHUB_TOKEN), the [credentials] config section or ~/.netrc",
+            status
+        );
+    }
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        return Ok(Attempt::Retry(anyhow::anyhow!("Failed to download repo zip: HTTP {}", status)));
+    }
+    if !status.is_success() {
+        anyhow::bail!("Failed to download repo zip: HTTP {}", status);
+    }
+
+    let header = |name| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from);
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+
+    if let Err(err) = download_file_with_progress(resp, dest_path) {
+        return Ok(Attempt::Retry(err));
+    }
+    Ok(Attempt::Done(FetchResult::Downloaded { etag, last_modified }))
+}
+
+/// Returns the path of an up to date archive in the download cache, downloading it if needed.
+///
+/// Cached archives are used as-is within the TTL, commit archives never change and are always
+/// reused. Past the TTL the archive is revalidated with `ETag` / `If-Modified-Since`, and a
+/// failed revalidation falls back to the cached copy.
+fn fetch_archive(
+    cache: &RepoCache,
+    repo_info: &RepoInfo,
+    download_url: &str,
+    credential: Option<&Credential>,
+    options: &FetchOptions,
+) -> anyhow::Result<PathBuf> {
+    let entry = cache.entry(&repo_info.cache_key());
+    let cached = entry.read_meta().filter(|meta| meta.url == download_url);
+
+    if options.offline {
+        if cached.is_none() {
+            anyhow::bail!(
+                "❌ '{}' is not in the download cache. Run without --offline to download it.",
+                entry.key
+            );
+        }
+        info_msg!("📦 Using cached archive (offline): {}", entry.key);
+        return Ok(entry.archive_path());
+    }
+
+    if let Some(meta) = &cached {
+        let immutable = matches!(repo_info.r#ref, GitRef::Commit(_));
+        if immutable || meta.is_fresh(options.cache.ttl) {
+            info_msg!("📦 Using cached archive: {}", entry.key);
+            return Ok(entry.archive_path());
+        }
+    }
+
+    let partial = entry.prepare_download()?;
+
+    let auth = credential.map(|credential| (&repo_info.platform, credential));
+
+    match download_zip_to_path(download_url, &partial, cached.as_ref(), auth, &options.network) {
+        Ok(FetchResult::NotModified) => {
+            let meta = CacheMeta { fetched_at: now_secs(), ..cached.unwrap_or_default() };
+            entry.write_meta(&meta)?;
+            info_msg!("📦 Cached archive is up to date: {}", entry.key);
+        }
+        Ok(FetchResult::Downloaded { etag, last_modified }) => {
+            let meta = CacheMeta {
+                url: download_url.to_string(),
+                etag,
+                last_modified,
+                fetched_at: now_secs(),
+            };
+            entry.commit_download(&meta)?;
+        }
+        Err(err) if cached.is_some() => {
+            warn_msg!("Failed to refresh '{}', using the cached archive: {:#}", entry.key, err);
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(entry.archive_path())
+}
+
+fn download_and_extract_zip(
+    cache: &RepoCache,
+    repo_info: &RepoInfo,
+    download_url: &str,
+    credential: Option<&Credential>,
+    sha256: Option<&str>,
+    options: &FetchOptions,
+) -> anyhow::Result<ExtractedRepo> {
+    let zip_path = fetch_archive(cache, repo_info, download_url, credential, options)?;
+    let actual = file_sha256(&zip_path)?;
+    if let Some(expected) = sha256 {
+        verify_checksum(&repo_info.cache_key(), expected, &actual)?;
+        info_msg!("🔒 Archive checksum verified: {}", actual);
+    }
+    let commit = archive_commit(&zip_path).or_else(|| match &repo_info.r#ref {
+        GitRef::Commit(sha) => Some(sha.clone()),
+        _ => None,
+    });
+
+    let tmp_dir = tempfile::tempdir().context("Failed to create temp dir")?;
     let extract_dir = tmp_dir.path().join("extract");
 
-    extract_zip(&zip_path, &extract_dir)?;
+    extract_zip(&zip_path, &extract_dir, &options.limits)?;
     let root_path = find_root_dir(&extract_dir)?;
     Ok(ExtractedRepo {
         root_dir: root_path,
+        commit,
+        sha256: Some(actual),
         _tmp_dir: tmp_dir, // 保持生命周期直到结构体 drop
     })
 }
 
-pub fn resolve_repo_to_dir(url: &String) -> anyhow::Result<ExtractedRepo> {
-    let repo_info = parse_repo_input(&url)?;
-    let download_url = repo_info.download_url();
+/// Picks the archive URL, authenticated GitHub downloads go through the REST API.
+fn archive_url(repo_info: &RepoInfo, credential: Option<&Credential>) -> String {
+    match (&repo_info.platform, credential) {
+        (RepoPlatform::GitHub, Some(_)) => repo_info.github_api_download_url(),
+        _ => repo_info.download_url(),
+    }
+}
+
+/// Fetches a repo with `git clone`, for remotes without an archive endpoint.
+fn clone_to_dir(repo_info: &RepoInfo, options: &FetchOptions) -> anyhow::Result<ExtractedRepo> {
+    let clone_url = repo_info.clone_url();
+    if options.offline && !clone_url.starts_with("file://") {
+        anyhow::bail!(
+            "❌ '{}' can only be fetched with git clone, which is not available with --offline.",
+            clone_url
+        );
+    }
+
+    let tmp_dir = tempfile::tempdir().context("Failed to create temp dir")?;
+    let root_dir = tmp_dir.path().join(&repo_info.repo);
+
+    info_msg!("📥 Cloning {}", clone_url);
+    let commit = clone_repo(&clone_url, &repo_info.r#ref, &root_dir)?;
+
+    Ok(ExtractedRepo { root_dir, commit: Some(commit), sha256: None, _tmp_dir: tmp_dir })
+}
+
+/// Points the extracted repo at its template subdirectory.
+fn enter_subdir(
+    mut extracted: ExtractedRepo,
+    subdir: &str,
+    url: &str,
+) -> anyhow::Result<ExtractedRepo> {
+    let relative = Path::new(subdir.trim_matches('/'));
+    let inside = relative.components().all(|c| matches!(c, std::path::Component::Normal(_)));
+    if !inside {
+        anyhow::bail!(
+            "❌ Repository subdirectory '{}' must be a relative path inside the repository",
+            subdir
+        );
+    }
+
+    let dir = extracted.root_dir.join(relative);
+    if !dir.is_dir() {
+        anyhow::bail!("❌ Subdirectory '{}' not found in repository '{}'", subdir, url);
+    }
+
+    extracted.root_dir = dir;
+    Ok(extracted)
+}
 
-    if download_url.is_empty() {
-        anyhow::bail!("Unsupported repo platform for direct zip download");
+/// Fetches a repo and returns the template directory in it.
+///
+/// `subdir` (the `repo_subdir` config field) wins over a subpath in the address itself. With a
+/// `sha256` the archive is verified before it is extracted.
+pub fn resolve_repo_to_dir(
+    url: &str,
+    subdir: Option<&str>,
+    sha256: Option<&str>,
+    options: &FetchOptions,
+) -> anyhow::Result<ExtractedRepo> {
+    let repo_info = parse_repo_input(url, &options.hosts)?;
+    let extracted = fetch_repo(&RepoCache::open_default()?, &repo_info, sha256, options)?;
+    if let Some(commit) = &extracted.commit {
+        info_msg!("📌 Resolved '{}' to commit {}", url, commit);
     }
 
-    let res = download_and_extract_zip(&download_url)?;
+    match subdir.or(repo_info.subdir.as_deref()) {
+        Some(subdir) => enter_subdir(extracted, subdir, url),
+        None => Ok(extracted),
+    }
+}
+
+fn fetch_repo(
+    cache: &RepoCache,
+    repo_info: &RepoInfo,
+    sha256: Option<&str>,
+    options: &FetchOptions,
+) -> anyhow::Result<ExtractedRepo> {
+    if repo_info.git_url.is_some() || repo_info.repo_api_url().is_none() {
+        if sha256.is_some() {
+            anyhow::bail!(
+                "❌ '{}' is fetched with git clone, sha256 checksums can only be verified for archive downloads",
+                repo_info.clone_url()
+            );
+        }
+        return clone_to_dir(repo_info, options);
+    }
+
+    let credential = resolve_credential(
+        &repo_info.platform,
+        repo_info.host(),
+        &options.hosts,
+        &options.credentials,
+    );
+    let download_url = match repo_info.r#ref {
+        GitRef::Default => {
+            default_branch_archive_url(cache, repo_info, credential.as_ref(), options)
+        }
+        _ => archive_url(repo_info, credential.as_ref()),
+    };
+
+    let res = download_and_extract_zip(
+        cache,
+        repo_info,
+        &download_url,
+        credential.as_ref(),
+        sha256,
+        options,
+    )?;
     Ok(res)
 }
 
@@ -221,7 +1096,7 @@ mod tests {
     #[test]
     fn test_parse_from_short_branch() {
         let input = "user/repo#dev".to_string();
-        let parsed = parse_repo_input(&input).unwrap();
+        let parsed = parse_repo_input(&input, &HashMap::new()).unwrap();
         assert_eq!(parsed.user, "user");
         assert_eq!(parsed.repo, "repo");
         assert_eq!(parsed.r#ref, GitRef::Branch("dev".to_string()));
@@ -230,14 +1105,14 @@ mod tests {
     #[test]
     fn test_parse_from_short_tag() {
         let input = "user/repo@v1.0.0".to_string();
-        let parsed = parse_repo_input(&input).unwrap();
+        let parsed = parse_repo_input(&input, &HashMap::new()).unwrap();
         assert_eq!(parsed.r#ref, GitRef::Tag("v1.0.0".to_string()));
     }
 
     #[test]
     fn test_parse_from_short_commit() {
         let input = "user/repo@0123456789abcdef0123456789abcdef01234567".to_string();
-        let parsed = parse_repo_input(&input).unwrap();
+        let parsed = parse_repo_input(&input, &HashMap::new()).unwrap();
         assert_eq!(
             parsed.r#ref,
             GitRef::Commit("0123456789abcdef0123456789abcdef01234567".to_string())
@@ -247,7 +1122,7 @@ mod tests {
     #[test]
     fn test_parse_from_url_github() {
         let input = "https://github.com/octocat/Hello-World.git".to_string();
-        let parsed = parse_repo_input(&input).unwrap();
+        let parsed = parse_repo_input(&input, &HashMap::new()).unwrap();
         assert_eq!(parsed.platform, RepoPlatform::GitHub);
         assert_eq!(parsed.user, "octocat");
         assert_eq!(parsed.repo, "Hello-World");
@@ -257,7 +1132,7 @@ mod tests {
     #[test]
     fn test_parse_from_url_with_ref() {
         let input = "https://github.com/octocat/Hello-World#dev".to_string();
-        let parsed = parse_repo_input(&input).unwrap();
+        let parsed = parse_repo_input(&input, &HashMap::new()).unwrap();
         assert_eq!(parsed.r#ref, GitRef::Branch("dev".to_string()));
     }
 
@@ -265,9 +1140,12 @@ mod tests {
     fn test_download_url_generation_github() {
         let repo = RepoInfo {
             platform: RepoPlatform::GitHub,
+            base_url: "https://github.com".to_string(),
             user: "octocat".to_string(),
             repo: "Hello-World".to_string(),
             r#ref: GitRef::Branch("main".to_string()),
+            git_url: None,
+            subdir: None,
         };
         let url = repo.download_url();
         assert_eq!(url, "https://github.com/octocat/Hello-World/archive/refs/heads/main.zip");
@@ -277,14 +1155,84 @@ mod tests {
     fn test_download_url_generation_gitlab() {
         let repo = RepoInfo {
             platform: RepoPlatform::GitLab,
+            base_url: "https://gitlab.com".to_string(),
             user: "gitlab-org".to_string(),
             repo: "gitlab".to_string(),
             r#ref: GitRef::Tag("v16.0".to_string()),
+            git_url: None,
+            subdir: None,
         };
         let url = repo.download_url();
         assert_eq!(url, "https://gitlab.com/gitlab-org/gitlab/-/archive/v16.0/gitlab-v16.0.zip");
     }
 
+    fn hosts(items: &[(&str, &str)]) -> HashMap<String, String> {
+        items.iter().map(|(h, p)| (h.to_string(), p.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_from_url_self_hosted() {
+        let hosts = hosts(&[("git.example.com:8443", "gitlab"), ("code.example.org", "forgejo")]);
+
+        let gitlab = parse_repo_input("https://git.example.com:8443/team/app#dev", &hosts).unwrap();
+        assert_eq!(gitlab.platform, RepoPlatform::GitLab);
+        assert_eq!(
+            gitlab.download_url(),
+            "https://git.example.com:8443/team/app/-/archive/dev/app-dev.zip"
+        );
+        assert_eq!(gitlab.cache_key(), "git.example.com%3A8443/team/app/branch-dev");
+
+        let forgejo = parse_repo_input("http://code.example.org/team/app@v1.0", &hosts).unwrap();
+        assert_eq!(forgejo.platform, RepoPlatform::Forgejo);
+        assert_eq!(forgejo.download_url(), "http://code.example.org/team/app/archive/v1.0.zip");
+    }
+
+    #[test]
+    fn test_parse_from_url_gitlab_nested_group() {
+        let hosts = hosts(&[("git.example.com", "gitlab")]);
+
+        let repo = parse_repo_input("https://git.example.com/group/sub/repo#main", &hosts).unwrap();
+        assert_eq!(repo.user, "group/sub");
+        assert_eq!(repo.repo, "repo");
+        assert_eq!(repo.subdir, None);
+        assert_eq!(
+            repo.download_url(),
+            "https://git.example.com/group/sub/repo/-/archive/main/repo-main.zip"
+        );
+        assert_eq!(
+            repo.repo_api_url().unwrap(),
+            "https://git.example.com/api/v4/projects/group%2Fsub%2Frepo"
+        );
+        assert_eq!(repo.cache_key(), "git.example.com/group%2Fsub/repo/branch-main");
+
+        let with_subdir =
+            parse_repo_input("https://git.example.com/group/sub/repo/-/templates/react", &hosts)
+                .unwrap();
+        assert_eq!(with_subdir.user, "group/sub");
+        assert_eq!(with_subdir.subdir.as_deref(), Some("templates/react"));
+    }
+
+    #[test]
+    fn test_parse_from_url_public_hosts() {
+        let bitbucket =
+            parse_repo_input("https://bitbucket.org/team/app#main", &HashMap::new()).unwrap();
+        assert_eq!(bitbucket.platform, RepoPlatform::Bitbucket);
+        assert_eq!(bitbucket.download_url(), "https://bitbucket.org/team/app/get/main.zip");
+        assert_eq!(bitbucket.cache_key(), "bitbucket/team/app/branch-main");
+
+        let codeberg = parse_repo_input("https://codeberg.org/team/app", &HashMap::new()).unwrap();
+        assert_eq!(codeberg.platform, RepoPlatform::Forgejo);
+    }
+
+    #[test]
+    fn test_parse_from_url_unknown_platform_name_should_fail() {
+        let hosts = hosts(&[("git.example.com", "svn")]);
+
+        let err = parse_repo_input("https://git.example.com/team/app", &hosts).unwrap_err();
+
+        assert!(err.to_string().contains("Unknown platform 'svn'"));
+    }
+
     #[test]
     fn test_extract_zip_and_find_root_dir() {
         let tmp = tempfile::tempdir().unwrap();
@@ -306,7 +1254,7 @@ mod tests {
         zip.finish().unwrap();
 
         let extract_dir = tmp.path().join("extract");
-        extract_zip(&zip_path, &extract_dir).unwrap();
+        extract_zip(&zip_path, &extract_dir, &ExtractLimits::default()).unwrap();
 
         // 验证解压后的根目录
         let root = find_root_dir(&extract_dir).unwrap();
@@ -318,10 +1266,615 @@ mod tests {
         assert_eq!(std::fs::read_to_string(sample_file).unwrap(), "Hello, world!");
     }
 
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])], method: zip::CompressionMethod) {
+        let file = std::fs::File::create(path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        for (name, content) in entries {
+            // A fixed time keeps the bytes of two archives with the same entries equal
+            let options: FileOptions<'_, ExtendedFileOptions> = FileOptions::default()
+                .compression_method(method)
+                .last_modified_time(zip::DateTime::default());
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn extract_err(entries: &[(&str, &[u8])], limits: &ExtractLimits) -> String {
+        let tmp = tempfile::tempdir().unwrap();
+        let zip_path = tmp.path().join("evil.zip");
+        write_zip(&zip_path, entries, zip::CompressionMethod::Deflated);
+        let result = extract_zip(&zip_path, &tmp.path().join("extract"), limits);
+        result.unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_extract_zip_rejects_path_traversal() {
+        let limits = ExtractLimits::default();
+
+        let err = extract_err(&[("root/../../evil.txt", b"evil")], &limits);
+        assert!(err.contains("escapes the extract directory"));
+
+        let err = extract_err(&[("/tmp/evil.txt", b"evil")], &limits);
+        assert!(err.contains("escapes the extract directory"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_zip_rejects_escaping_symlink() {
+        let tmp = tempfile::tempdir().unwrap();
+        let zip_path = tmp.path().join("evil.zip");
+        let file = std::fs::File::create(&zip_path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        let options: FileOptions<'_, ExtendedFileOptions> = FileOptions::default();
+        zip.add_symlink("root/link", "../../outside", options).unwrap();
+        zip.finish().unwrap();
+
+        let result = extract_zip(&zip_path, &tmp.path().join("extract"), &ExtractLimits::default());
+
+        assert!(result.unwrap_err().to_string().contains("symlink escaping"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_zip_rejects_symlink_chains() {
+        let tmp = tempfile::tempdir().unwrap();
+        let zip_path = tmp.path().join("evil.zip");
+        let file = std::fs::File::create(&zip_path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        let options: FileOptions<'_, ExtendedFileOptions> = FileOptions::default();
+        zip.add_symlink("root/s2", "s1/s1/s1/../../../..", options.clone()).unwrap();
+        zip.add_symlink("root/s1", ".", options.clone()).unwrap();
+        zip.finish().unwrap();
+
+        let result = extract_zip(&zip_path, &tmp.path().join("extract"), &ExtractLimits::default());
+
+        assert!(result.unwrap_err().to_string().contains("'root/s2' is a symlink escaping"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_zip_rejects_entries_below_symlinks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let zip_path = tmp.path().join("evil.zip");
+        let file = std::fs::File::create(&zip_path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        let options: FileOptions<'_, ExtendedFileOptions> = FileOptions::default();
+        zip.add_symlink("root/s1", ".", options.clone()).unwrap();
+        zip.start_file("root/s1/evil", options).unwrap();
+        zip.write_all(b"evil").unwrap();
+        zip.finish().unwrap();
+
+        let result = extract_zip(&zip_path, &tmp.path().join("extract"), &ExtractLimits::default());
+
+        assert!(result.unwrap_err().to_string().contains("below the symlink 'root/s1'"));
+        assert!(!tmp.path().join("extract/root/evil").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_zip_bounds_symlink_targets() {
+        let tmp = tempfile::tempdir().unwrap();
+        let zip_path = tmp.path().join("evil.zip");
+        let file = std::fs::File::create(&zip_path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        let options: FileOptions<'_, ExtendedFileOptions> =
+            FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        zip.add_symlink("root/bomb", "a".repeat(16 * 1024 * 1024), options).unwrap();
+        zip.finish().unwrap();
+
+        let result = extract_zip(&zip_path, &tmp.path().join("extract"), &ExtractLimits::default());
+        assert!(result.unwrap_err().to_string().contains("target longer than 4096 bytes"));
+
+        let zip_path = tmp.path().join("small.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        let options: FileOptions<'_, ExtendedFileOptions> = FileOptions::default();
+        zip.add_symlink("root/link", "target.txt", options).unwrap();
+        zip.finish().unwrap();
+
+        let limits = ExtractLimits { max_total_size: 4, ..Default::default() };
+        let result = extract_zip(&zip_path, &tmp.path().join("small"), &limits);
+        assert!(result.unwrap_err().to_string().contains("extract.max_total_size"));
+    }
+
+    #[test]
+    fn test_extract_zip_enforces_file_count() {
+        let limits = ExtractLimits { max_files: 2, ..Default::default() };
+
+        let err = extract_err(&[("a", b"a"), ("b", b"b"), ("c", b"c")], &limits);
+
+        assert!(err.contains("3 entries"));
+        assert!(err.contains("extract.max_files"));
+    }
+
+    #[test]
+    fn test_extract_zip_enforces_total_size() {
+        let limits = ExtractLimits { max_total_size: 16, ..Default::default() };
+
+        let err = extract_err(&[("a.txt", &[b'a'; 10]), ("b.txt", &[b'b'; 10])], &limits);
+
+        assert!(err.contains("extract.max_total_size"));
+    }
+
+    #[test]
+    fn test_extract_zip_enforces_compression_ratio() {
+        let zeros = vec![0u8; 4 * 1024 * 1024];
+
+        let err = extract_err(&[("bomb.bin", &zeros)], &ExtractLimits::default());
+
+        assert!(err.contains("bomb.bin"));
+        assert!(err.contains("extract.max_compression_ratio"));
+    }
+
+    #[test]
+    fn test_extract_zip_stops_writing_at_compression_ratio() {
+        let tmp = tempfile::tempdir().unwrap();
+        let zip_path = tmp.path().join("bomb.zip");
+        write_zip(
+            &zip_path,
+            &[("bomb.bin", &vec![0u8; 8 * 1024 * 1024])],
+            zip::CompressionMethod::Deflated,
+        );
+        let extract_dir = tmp.path().join("extract");
+
+        assert!(extract_zip(&zip_path, &extract_dir, &ExtractLimits::default()).is_err());
+
+        // 8 MiB of zeros deflate to about 8 KiB, allowing 200 times that before stopping
+        let written = std::fs::metadata(extract_dir.join("bomb.bin")).unwrap().len();
+        assert!(written < 2 * 1024 * 1024);
+    }
+
+    fn seeded_cache(repo_info: &RepoInfo, url: &str, fetched_at: u64) -> (TempDir, RepoCache) {
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = RepoCache::new(tmp.path().to_path_buf());
+        let entry = cache.entry(&repo_info.cache_key());
+        write_zip(
+            &entry.prepare_download().unwrap(),
+            &[("root/a.txt", b"a")],
+            zip::CompressionMethod::Stored,
+        );
+        entry
+            .commit_download(&CacheMeta { url: url.to_string(), fetched_at, ..Default::default() })
+            .unwrap();
+        (tmp, cache)
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let repo = parse_repo_input("octocat/Hello-World#feature/login", &HashMap::new()).unwrap();
+        assert_eq!(repo.cache_key(), "github/octocat/Hello-World/branch-feature%2Flogin");
+    }
+
+    #[test]
+    fn test_fetch_archive_offline_uses_cache_only() {
+        let repo = parse_repo_input("octocat/Hello-World", &HashMap::new()).unwrap();
+        let url = repo.download_url();
+        let options = FetchOptions { offline: true, ..Default::default() };
+
+        let empty = RepoCache::new(tempfile::tempdir().unwrap().path().join("cache"));
+        let err = fetch_archive(&empty, &repo, &url, None, &options).unwrap_err().to_string();
+        assert!(err.contains("not in the download cache"));
+
+        let (_tmp, cache) = seeded_cache(&repo, &url, 0);
+        let archive = fetch_archive(&cache, &repo, &url, None, &options).unwrap();
+        assert_eq!(archive, cache.entry(&repo.cache_key()).archive_path());
+    }
+
+    #[test]
+    fn test_fetch_archive_reuses_fresh_and_commit_entries() {
+        // Unreachable URLs: any network access would fail the fetch
+        let fresh = parse_repo_input("octocat/Hello-World", &HashMap::new()).unwrap();
+        let (_tmp, cache) = seeded_cache(&fresh, "http://127.0.0.1:9/a.zip", now_secs());
+        fetch_archive(&cache, &fresh, "http://127.0.0.1:9/a.zip", None, &FetchOptions::default())
+            .unwrap();
+
+        let commit = parse_repo_input(
+            "octocat/Hello-World@0123456789abcdef0123456789abcdef01234567",
+            &HashMap::new(),
+        )
+        .unwrap();
+        let (_tmp, cache) = seeded_cache(&commit, "http://127.0.0.1:9/b.zip", 0);
+        fetch_archive(&cache, &commit, "http://127.0.0.1:9/b.zip", None, &FetchOptions::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_fetch_archive_falls_back_to_stale_cache() {
+        let repo = parse_repo_input("octocat/Hello-World", &HashMap::new()).unwrap();
+        let (_tmp, cache) = seeded_cache(&repo, "http://127.0.0.1:9/a.zip", 0);
+
+        let network = NetworkSettings { retries: 0, ..Default::default() };
+        let options = FetchOptions { network, ..Default::default() };
+        let archive =
+            fetch_archive(&cache, &repo, "http://127.0.0.1:9/a.zip", None, &options).unwrap();
+
+        assert!(archive.is_file());
+    }
+
+    /// A stand-in HTTP server answering each request with the next response, returns its base
+    /// URL and a handle yielding the raw requests.
+    fn serve(responses: Vec<(u16, Vec<u8>)>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let responses = responses
+            .into_iter()
+            .map(|(status, body)| {
+                let head = format!(
+                    "HTTP/1.1 {} Stand-in\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                [head.into_bytes(), body].concat()
+            })
+            .collect();
+        serve_raw(responses)
+    }
+
+    /// Like [`serve`], with the responses written byte for byte.
+    fn serve_raw(responses: Vec<Vec<u8>>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        use std::io::Read as _;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut raw = vec![];
+                let mut buf = [0; 1024];
+                while !raw.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    raw.extend_from_slice(&buf[..n]);
+                }
+                requests.push(String::from_utf8_lossy(&raw).to_lowercase());
+                stream.write_all(&response).unwrap();
+            }
+            requests
+        });
+
+        (base, handle)
+    }
+
+    fn zip_bytes() -> Vec<u8> {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.zip");
+        write_zip(&path, &[("root/a.txt", b"a")], zip::CompressionMethod::Stored);
+        std::fs::read(path).unwrap()
+    }
+
+    fn fetch_from(
+        cache: &RepoCache,
+        base: &str,
+        platform: &str,
+        token: Option<&str>,
+    ) -> anyhow::Result<PathBuf> {
+        let host = base.trim_start_matches("http://").to_string();
+        let hosts = HashMap::from([(host.clone(), platform.to_string())]);
+        let credentials = token
+            .map(|t| HashMap::from([(host, Credential { username: None, token: t.into() })]))
+            .unwrap_or_default();
+
+        let repo = parse_repo_input(&format!("{}/team/app#main", base), &hosts).unwrap();
+        let credential = resolve_credential(&repo.platform, repo.host(), &hosts, &credentials);
+        let url = archive_url(&repo, credential.as_ref());
+
+        fetch_archive(cache, &repo, &url, credential.as_ref(), &FetchOptions::default())
+    }
+
+    #[test]
+    fn test_private_gitlab_download_sends_private_token() {
+        let (base, server) = serve(vec![(200, zip_bytes())]);
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = RepoCache::new(tmp.path().to_path_buf());
+
+        let archive = fetch_from(&cache, &base, "gitlab", Some("s3cret")).unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(archive.is_file());
+        assert!(requests[0].starts_with("get /team/app/-/archive/main/app-main.zip"));
+        assert!(requests[0].contains("private-token: s3cret"));
+    }
+
+    #[test]
+    fn test_private_github_download_uses_api_with_bearer() {
+        let (base, server) = serve(vec![(200, zip_bytes())]);
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = RepoCache::new(tmp.path().to_path_buf());
+
+        fetch_from(&cache, &base, "github", Some("s3cret")).unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("get /api/v3/repos/team/app/zipball/main"));
+        assert!(requests[0].contains("authorization: bearer s3cret"));
+    }
+
+    #[test]
+    fn test_private_download_errors_never_show_token() {
+        let (base, server) = serve(vec![(404, vec![]), (404, vec![])]);
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = RepoCache::new(tmp.path().to_path_buf());
+
+        let with_token = fetch_from(&cache, &base, "gitea", Some("s3cret")).unwrap_err();
+        let without_token = fetch_from(&cache, &base, "gitea", None).unwrap_err();
+        let (with_token, without_token) =
+            (format!("{:#}", with_token), format!("{:#}", without_token));
+
+        server.join().unwrap();
+        assert!(with_token.contains("HTTP 404"));
+        assert!(!with_token.contains("s3cret"));
+        assert!(without_token.contains("Private repositories need a token"));
+    }
+
+    const SHA: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    /// An archive like `git archive` produces, with the commit id as zip comment.
+    fn commit_zip_bytes() -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(io::Cursor::new(vec![]));
+        let options: FileOptions<'_, ExtendedFileOptions> = FileOptions::default();
+        zip.start_file("app-master/a.txt", options).unwrap();
+        zip.write_all(b"a").unwrap();
+        zip.set_comment(SHA);
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn fetch_default(cache: &RepoCache, base: &str, platform: &str) -> ExtractedRepo {
+        let hosts =
+            HashMap::from([(base.trim_start_matches("http://").to_string(), platform.into())]);
+        let repo = parse_repo_input(&format!("{}/team/app", base), &hosts).unwrap();
+        fetch_repo(cache, &repo, None, &FetchOptions { hosts, ..Default::default() }).unwrap()
+    }
+
+    #[test]
+    fn test_github_tag_and_commit_download_urls() {
+        let tag = parse_repo_input("octocat/Hello-World@v1.0.0", &HashMap::new()).unwrap();
+        assert_eq!(
+            tag.download_url(),
+            "https://github.com/octocat/Hello-World/archive/refs/tags/v1.0.0.zip"
+        );
+
+        let commit =
+            parse_repo_input(&format!("octocat/Hello-World@{}", SHA), &HashMap::new()).unwrap();
+        assert_eq!(
+            commit.download_url(),
+            format!("https://github.com/octocat/Hello-World/archive/{}.zip", SHA)
+        );
+    }
+
+    #[test]
+    fn test_default_branch_is_looked_up() {
+        let api = br#"{ "id": 1, "default_branch": "master" }"#.to_vec();
+        let (base, server) = serve(vec![(200, api), (200, commit_zip_bytes())]);
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = RepoCache::new(tmp.path().to_path_buf());
+
+        let repo = fetch_default(&cache, &base, "gitlab");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("get /api/v4/projects/team%2fapp "));
+        assert!(requests[1].starts_with("get /team/app/-/archive/master/app-master.zip"));
+        assert_eq!(repo.commit.as_deref(), Some(SHA));
+        assert!(repo.root_dir.join("a.txt").is_file());
+    }
+
+    #[test]
+    fn test_failed_default_branch_lookup_falls_back_to_main() {
+        let (base, server) = serve(vec![(404, vec![]), (200, commit_zip_bytes())]);
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = RepoCache::new(tmp.path().to_path_buf());
+
+        fetch_default(&cache, &base, "gitea");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("get /api/v1/repos/team/app "));
+        assert!(requests[1].starts_with("get /team/app/archive/main.zip"));
+    }
+
+    #[test]
+    fn test_fresh_default_archive_skips_lookup() {
+        // Nothing listens on the host, any lookup or download would fail
+        let base = "http://127.0.0.1:9";
+        let hosts = HashMap::from([("127.0.0.1:9".to_string(), "gitea".to_string())]);
+        let repo = parse_repo_input(&format!("{}/team/app", base), &hosts).unwrap();
+        let (_tmp, cache) =
+            seeded_cache(&repo, "http://127.0.0.1:9/team/app/archive/dev.zip", now_secs());
+
+        let extracted =
+            fetch_repo(&cache, &repo, None, &FetchOptions { hosts, ..Default::default() });
+
+        assert!(extracted.unwrap().root_dir.join("a.txt").is_file());
+    }
+
+    fn download(url: &str, network: NetworkSettings) -> anyhow::Result<Vec<u8>> {
+        let tmp = tempfile::tempdir().unwrap();
+        let dest = tmp.path().join("a.zip");
+        download_zip_to_path(url, &dest, None, None, &network)?;
+        Ok(fs::read(dest).unwrap())
+    }
+
+    fn quick_retries(retries: u32) -> NetworkSettings {
+        NetworkSettings { retries, retry_delay: 1, ..Default::default() }
+    }
+
+    #[test]
+    fn test_download_retries_server_errors() {
+        let zip = zip_bytes();
+        let (base, server) = serve(vec![(503, vec![]), (429, vec![]), (200, zip.clone())]);
+
+        let body = download(&format!("{}/a.zip", base), quick_retries(2)).unwrap();
+
+        assert_eq!(server.join().unwrap().len(), 3);
+        assert_eq!(body, zip);
+    }
+
+    #[test]
+    fn test_download_gives_up_after_retries() {
+        let (base, server) = serve(vec![(502, vec![]), (502, vec![])]);
+
+        let err = download(&format!("{}/a.zip", base), quick_retries(1)).unwrap_err();
+
+        server.join().unwrap();
+        assert!(format!("{:#}", err).contains("Download failed after 2 attempt(s)"));
+        assert!(format!("{:#}", err).contains("HTTP 502"));
+    }
+
+    #[test]
+    fn test_truncated_download_should_fail() {
+        let truncated =
+            b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\nConnection: close\r\n\r\n0123456789";
+        let (base, server) = serve_raw(vec![truncated.to_vec()]);
+
+        let err = download(&format!("{}/a.zip", base), quick_retries(0)).unwrap_err();
+
+        server.join().unwrap();
+        let err = format!("{:#}", err);
+        assert!(
+            err.contains("Download truncated") || err.contains("Download interrupted"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_download_without_content_length() {
+        let head = b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n".to_vec();
+        let zip = zip_bytes();
+        let (base, server) = serve_raw(vec![[head, zip.clone()].concat()]);
+
+        let body = download(&format!("{}/a.zip", base), quick_retries(0)).unwrap();
+
+        server.join().unwrap();
+        assert_eq!(body, zip);
+    }
+
+    #[test]
+    fn test_download_through_configured_proxy() {
+        let (proxy, server) = serve(vec![(200, zip_bytes())]);
+        let network = NetworkSettings { proxy: Some(proxy), ..quick_retries(0) };
+
+        download("http://templates.invalid/team/app/archive/main.zip", network).unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("get http://templates.invalid/team/app/archive/main.zip"));
+    }
+
+    #[test]
+    fn test_file_sha256_and_verify_checksum() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.zip");
+        fs::write(&path, b"abc").unwrap();
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        assert_eq!(file_sha256(&path).unwrap(), abc);
+        assert!(verify_checksum("a", &format!("sha256:{}", abc.to_uppercase()), abc).is_ok());
+
+        let err = verify_checksum("github/a/b/default", "00ff", abc).unwrap_err().to_string();
+        assert!(err.contains("Checksum mismatch for 'github/a/b/default'"));
+        assert!(err.contains("expected: 00ff"));
+        assert!(err.contains(&format!("actual:   {}", abc)));
+    }
+
+    #[test]
+    fn test_checksum_on_git_remote_should_fail() {
+        let repo = parse_repo_input("file:///srv/git/template.git", &HashMap::new()).unwrap();
+        let cache = RepoCache::new(tempfile::tempdir().unwrap().path().to_path_buf());
+
+        let err = fetch_repo(&cache, &repo, Some("00ff"), &FetchOptions::default()).err().unwrap();
+
+        assert!(err.to_string().contains("only be verified for archive downloads"));
+    }
+
+    #[test]
+    fn test_parse_git_remotes() {
+        let scp = parse_repo_input("git@github.com:user/repo.git#dev", &HashMap::new()).unwrap();
+        assert_eq!(scp.platform, RepoPlatform::GitHub);
+        assert_eq!((scp.user.as_str(), scp.repo.as_str()), ("user", "repo"));
+        assert_eq!(scp.r#ref, GitRef::Branch("dev".into()));
+        assert_eq!(scp.clone_url(), "git@github.com:user/repo.git");
+
+        let ssh = parse_repo_input(
+            "ssh://git@git.example.com:2222/group/sub/app.git@v1",
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(ssh.platform, RepoPlatform::Other("git.example.com:2222".into()));
+        assert_eq!((ssh.user.as_str(), ssh.repo.as_str()), ("group/sub", "app"));
+        assert_eq!(ssh.r#ref, GitRef::Tag("v1".into()));
+        assert_eq!(ssh.clone_url(), "ssh://git@git.example.com:2222/group/sub/app.git");
+
+        let file = parse_repo_input("file:///srv/git/template.git", &HashMap::new()).unwrap();
+        assert_eq!(file.platform, RepoPlatform::Other("local".into()));
+        assert_eq!(file.repo, "template");
+        assert_eq!(file.r#ref, GitRef::Default);
+
+        // the short form with a tag is not mistaken for an scp-like remote
+        let short = parse_repo_input("user/repo@v1", &HashMap::new()).unwrap();
+        assert_eq!(short.git_url, None);
+    }
+
+    #[test]
+    fn test_parse_subdir() {
+        let short = parse_repo_input("user/repo/templates/react#main", &HashMap::new()).unwrap();
+        assert_eq!(short.repo, "repo");
+        assert_eq!(short.subdir.as_deref(), Some("templates/react"));
+        assert_eq!(short.r#ref, GitRef::Branch("main".into()));
+
+        let url = parse_repo_input("https://github.com/org/app/templates/vue/@v2", &HashMap::new())
+            .unwrap();
+        assert_eq!(url.subdir.as_deref(), Some("templates/vue"));
+        assert_eq!(url.r#ref, GitRef::Tag("v2".into()));
+
+        let gitlab =
+            parse_repo_input("https://gitlab.com/org/app/-/templates/vue/@v2", &HashMap::new())
+                .unwrap();
+        assert_eq!((gitlab.user.as_str(), gitlab.repo.as_str()), ("org", "app"));
+        assert_eq!(gitlab.subdir.as_deref(), Some("templates/vue"));
+
+        let plain = parse_repo_input("user/repo/", &HashMap::new()).unwrap();
+        assert_eq!(plain.subdir, None);
+    }
+
+    #[test]
+    fn test_resolve_repo_to_dir_subdir() {
+        let (_temp, bare, _) = crate::helper::git::tests::bare_repo();
+        let url = format!("file://{}", bare.display());
+        let options = FetchOptions { offline: true, ..Default::default() };
+
+        let repo = resolve_repo_to_dir(&url, Some("templates/react"), None, &options).unwrap();
+        assert_eq!(fs::read_to_string(repo.root_dir.join("README.md")).unwrap(), "react");
+
+        let missing =
+            resolve_repo_to_dir(&url, Some("templates/vue"), None, &options).err().unwrap();
+        assert!(missing.to_string().contains("Subdirectory 'templates/vue' not found"));
+
+        let escaping = resolve_repo_to_dir(&url, Some("../.."), None, &options).err().unwrap();
+        assert!(escaping.to_string().contains("must be a relative path"));
+    }
+
+    #[test]
+    fn test_other_host_clone_url() {
+        let repo = parse_repo_input("https://git.example.com/org/app", &HashMap::new()).unwrap();
+        assert_eq!(repo.clone_url(), "https://git.example.com/org/app.git");
+    }
+
+    #[test]
+    fn test_resolve_repo_to_dir_clones_git_remote() {
+        let (_temp, bare, _) = crate::helper::git::tests::bare_repo();
+        let url = format!("file://{}#dev", bare.display());
+        let options = FetchOptions { offline: true, ..Default::default() };
+
+        let repo = resolve_repo_to_dir(&url, None, None, &options).unwrap();
+
+        assert_eq!(fs::read_to_string(repo.root_dir.join("README.md")).unwrap(), "dev");
+        assert!(!repo.root_dir.join(".git").exists());
+    }
+
     #[test]
     fn test_invalid_url_should_fail() {
         let input = "invalid_url".to_string();
-        let result = parse_repo_input(&input);
+        let result = parse_repo_input(&input, &HashMap::new());
         assert!(result.is_err());
     }
 
@@ -329,9 +1882,12 @@ mod tests {
     fn test_empty_download_url_should_fail() {
         let repo = RepoInfo {
             platform: RepoPlatform::Other("unknown.com".to_string()),
+            base_url: "https://unknown.com".to_string(),
             user: "foo".to_string(),
             repo: "bar".to_string(),
             r#ref: GitRef::Default,
+            git_url: None,
+            subdir: None,
         };
         assert_eq!(repo.download_url(), "");
     }
@@ -339,7 +1895,7 @@ mod tests {
     #[test]
     fn test_resolve_repo_to_dir_real_github() {
         let url = "https://github.com/octocat/Hello-World#master".to_string();
-        let repo = resolve_repo_to_dir(&url).unwrap();
+        let repo = resolve_repo_to_dir(&url, None, None, &FetchOptions::default()).unwrap();
         assert!(repo.root_dir.exists());
         assert!(repo.root_dir.is_dir());
     }
@@ -0,0 +1,1006 @@
+use std::{collections::HashMap, fmt, iter::Peekable, vec::IntoIter};
+
+/// A value visible to template expressions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Bool(bool),
+    Int(i64),
+}
+
+impl Value {
+    /// `false`, `0` and empty strings are falsy, as are the strings `false`, `no`,
+    /// `off` and `0` so answers given as text work in conditions.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Bool(value) => *value,
+            Value::Int(value) => *value != 0,
+            Value::Str(value) => !matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "" | "false" | "no" | "off" | "0"
+            ),
+        }
+    }
+
+    /// Items a `{% for %}` loop walks over, strings are split on commas.
+    pub fn items(&self) -> Vec<Value> {
+        match self {
+            Value::Str(value) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::Str(item.to_string()))
+                .collect(),
+            other => vec![other.clone()],
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(value) => f.write_str(value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Variables available while rendering, keyed by name.
+pub type Context = HashMap<String, Value>;
+
+/// A syntax or evaluation error, `line` is 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+const BLOCK_KEYWORDS: [&str; 8] = ["if", "elif", "else", "endif", "for", "endfor", "raw", "endraw"];
+
+/// Renders `source` with the variables in `context`.
+///
+/// Supports `{{ expr | filter }}` output, `{% if %}` / `{% elif %}` / `{% else %}` blocks,
+/// `{% for item in list %}` loops, `{# comments #}` and `{% raw %}` sections. Output tags and
+/// `if` / `for` blocks that use unknown variables or are not valid expressions are kept as they
+/// are, so files written for other template languages pass through.
+pub fn render(source: &str, context: &Context) -> Result<String, TemplateError> {
+    if !["{{", "{%", "{#"].iter().any(|open| source.contains(open)) {
+        return Ok(source.to_string());
+    }
+
+    let mut tokens = tokenize(source)?.into_iter().peekable();
+    let (nodes, end) = parse_nodes(&mut tokens, &[])?;
+    if let Some(tag) = end {
+        return Err(tag.error(format!("unexpected {{% {} %}}", tag.keyword)));
+    }
+
+    let mut scope = Scope { context, locals: vec![] };
+    let mut output = String::with_capacity(source.len());
+    render_nodes(&nodes, &mut scope, &mut output)?;
+    Ok(output)
+}
+
+/// Evaluates a single expression such as `docker == true` or `name | snake_case`.
+pub fn evaluate(expression: &str, context: &Context) -> Result<Value, TemplateError> {
+    let expr =
+        parse_expression(expression).map_err(|message| TemplateError { line: 1, message })?;
+    let scope = Scope { context, locals: vec![] };
+    scope.eval(&expr).map_err(|message| TemplateError { line: 1, message })
+}
+
+/// Names of the variables an expression uses, in order of appearance.
+pub fn variables(expression: &str) -> Result<Vec<String>, TemplateError> {
+    let expr =
+        parse_expression(expression).map_err(|message| TemplateError { line: 1, message })?;
+    let mut names = vec![];
+    expr.collect_variables(&mut names);
+    Ok(names)
+}
+
+enum Token {
+    Text(String),
+    Output { source: String, expression: String, line: usize },
+    Tag { keyword: String, rest: String, source: String, line: usize },
+}
+
+/// Splits the source into text, output tags and block tags.
+///
+/// Block tags and comments alone on their line take the whole line with them, so they do not
+/// leave blank lines behind. Tags with keywords the engine does not know stay plain text.
+fn tokenize(source: &str) -> Result<Vec<Token>, TemplateError> {
+    let mut tokens = vec![];
+    let mut text = String::new();
+    let mut pos = 0;
+    let mut line = 1;
+
+    while let Some(start) = find_open(source, pos) {
+        let open = &source[start..start + 2];
+        let close = match open {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}",
+        };
+        let Some(len) = source[start + 2..].find(close) else {
+            text.push_str(&source[pos..start + 2]);
+            line += source[pos..start].matches('\n').count();
+            pos = start + 2;
+            continue;
+        };
+        let end = start + 2 + len + 2;
+        let inner = source[start + 2..start + 2 + len].trim();
+
+        text.push_str(&source[pos..start]);
+        line += source[pos..start].matches('\n').count();
+        let tag_line = line;
+
+        let keyword = inner.split_whitespace().next().unwrap_or("");
+        let is_block = open == "{#" || (open == "{%" && BLOCK_KEYWORDS.contains(&keyword));
+        if open == "{%" && !is_block {
+            text.push_str(&source[start..end]);
+            line += source[start..end].matches('\n').count();
+            pos = end;
+            continue;
+        }
+
+        let mut next = end;
+        let mut tag_start = start;
+        if is_block && let Some(after) = standalone_end(source, start, end) {
+            let indent = text.len() - text.trim_end_matches([' ', '\t']).len();
+            text.truncate(text.len() - indent);
+            tag_start -= indent;
+            next = after;
+        }
+        line += source[start..next].matches('\n').count();
+
+        match open {
+            "{{" => {
+                push_text(&mut tokens, &mut text);
+                tokens.push(Token::Output {
+                    source: source[start..end].to_string(),
+                    expression: inner.to_string(),
+                    line: tag_line,
+                });
+            }
+            "{#" => {}
+            _ if keyword == "raw" => {
+                let Some((close_start, close_end)) = find_endraw(source, next) else {
+                    return Err(TemplateError {
+                        line: tag_line,
+                        message: "unclosed {% raw %} block".to_string(),
+                    });
+                };
+                text.push_str(&source[next..close_start]);
+                line += source[next..close_start].matches('\n').count();
+                let after = standalone_end(source, close_start, close_end).unwrap_or(close_end);
+                if after != close_end {
+                    let indent = text.len() - text.trim_end_matches([' ', '\t']).len();
+                    text.truncate(text.len() - indent);
+                }
+                line += source[close_start..after].matches('\n').count();
+                next = after;
+            }
+            _ => {
+                push_text(&mut tokens, &mut text);
+                tokens.push(Token::Tag {
+                    keyword: keyword.to_string(),
+                    rest: inner[keyword.len()..].trim().to_string(),
+                    source: source[tag_start..next].to_string(),
+                    line: tag_line,
+                });
+            }
+        }
+        pos = next;
+    }
+
+    text.push_str(&source[pos..]);
+    push_text(&mut tokens, &mut text);
+    Ok(tokens)
+}
+
+fn push_text(tokens: &mut Vec<Token>, text: &mut String) {
+    if !text.is_empty() {
+        tokens.push(Token::Text(std::mem::take(text)));
+    }
+}
+
+fn find_open(source: &str, from: usize) -> Option<usize> {
+    ["{{", "{%", "{#"]
+        .iter()
+        .filter_map(|open| source[from..].find(open).map(|index| from + index))
+        .min()
+}
+
+/// Returns the end of the line when the tag at `start..end` is the only thing on it.
+fn standalone_end(source: &str, start: usize, end: usize) -> Option<usize> {
+    let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+    if !source[line_start..start].chars().all(|c| c == ' ' || c == '\t') {
+        return None;
+    }
+    let rest = &source[end..];
+    let trailing = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    let rest = &rest[trailing..];
+    if rest.is_empty() {
+        Some(source.len())
+    } else if rest.starts_with("\r\n") {
+        Some(end + trailing + 2)
+    } else if rest.starts_with('\n') {
+        Some(end + trailing + 1)
+    } else {
+        None
+    }
+}
+
+/// Finds the start and end of the `{% endraw %}` tag after `from`.
+fn find_endraw(source: &str, from: usize) -> Option<(usize, usize)> {
+    let mut pos = from;
+    while let Some(index) = source[pos..].find("{%") {
+        let start = pos + index;
+        let len = source[start + 2..].find("%}")?;
+        let end = start + 2 + len + 2;
+        if source[start + 2..start + 2 + len].trim() == "endraw" {
+            return Some((start, end));
+        }
+        pos = start + 2;
+    }
+    None
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Output { expr: Expr, source: String, line: usize },
+    If { branches: Vec<Branch>, otherwise: Option<(String, Vec<Node>)>, end: String },
+    For(Box<ForLoop>),
+}
+
+/// An `if` or `elif` branch, `condition` is `None` when it is not a valid expression.
+#[derive(Debug)]
+struct Branch {
+    tag: String,
+    condition: Option<Expr>,
+    line: usize,
+    body: Vec<Node>,
+}
+
+/// A `for` loop, `header` is `None` when the tag is not `for item in <expression>`.
+#[derive(Debug)]
+struct ForLoop {
+    tag: String,
+    header: Option<(String, Expr)>,
+    line: usize,
+    body: Vec<Node>,
+    otherwise: Option<(String, Vec<Node>)>,
+    end: String,
+}
+
+struct EndTag {
+    keyword: String,
+    rest: String,
+    source: String,
+    line: usize,
+}
+
+impl EndTag {
+    fn error(&self, message: String) -> TemplateError {
+        TemplateError { line: self.line, message }
+    }
+}
+
+/// Parses nodes until one of the `ends` tags, which is returned, or the end of the input.
+fn parse_nodes(
+    tokens: &mut Peekable<IntoIter<Token>>,
+    ends: &[&str],
+) -> Result<(Vec<Node>, Option<EndTag>), TemplateError> {
+    let mut nodes = vec![];
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text)),
+            Token::Output { source, expression, line } => match parse_expression(&expression) {
+                Ok(expr) => nodes.push(Node::Output { expr, source, line }),
+                Err(_) => nodes.push(Node::Text(source)),
+            },
+            Token::Tag { keyword, rest, source, line } => match keyword.as_str() {
+                "if" => nodes.push(parse_if(tokens, &rest, source, line)?),
+                "for" => nodes.push(parse_for(tokens, &rest, source, line)?),
+                _ if ends.contains(&keyword.as_str()) => {
+                    return Ok((nodes, Some(EndTag { keyword, rest, source, line })));
+                }
+                _ => {
+                    return Err(TemplateError {
+                        line,
+                        message: format!("unexpected {{% {} %}}", keyword),
+                    });
+                }
+            },
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn parse_if(
+    tokens: &mut Peekable<IntoIter<Token>>,
+    condition: &str,
+    tag: String,
+    line: usize,
+) -> Result<Node, TemplateError> {
+    let mut branches = vec![];
+    let mut branch = (tag, parse_expression(condition).ok(), line);
+
+    loop {
+        let (body, end) = parse_nodes(tokens, &["elif", "else", "endif"])?;
+        let (tag, condition, branch_line) = branch;
+        branches.push(Branch { tag, condition, line: branch_line, body });
+
+        let Some(end) = end else {
+            return Err(unclosed("if", line));
+        };
+        match end.keyword.as_str() {
+            "elif" => branch = (end.source, parse_expression(&end.rest).ok(), end.line),
+            "else" => {
+                let (otherwise, endif) = parse_nodes(tokens, &["endif"])?;
+                let Some(endif) = endif else {
+                    return Err(unclosed("if", line));
+                };
+                let otherwise = Some((end.source, otherwise));
+                return Ok(Node::If { branches, otherwise, end: endif.source });
+            }
+            _ => return Ok(Node::If { branches, otherwise: None, end: end.source }),
+        }
+    }
+}
+
+fn parse_for(
+    tokens: &mut Peekable<IntoIter<Token>>,
+    rest: &str,
+    tag: String,
+    line: usize,
+) -> Result<Node, TemplateError> {
+    let header = rest.split_once(" in ").and_then(|(name, iterable)| {
+        let name = name.trim();
+        let iterable = parse_expression(iterable).ok()?;
+        is_identifier(name).then(|| (name.to_string(), iterable))
+    });
+
+    let (body, end) = parse_nodes(tokens, &["else", "endfor"])?;
+    let Some(mut end) = end else {
+        return Err(unclosed("for", line));
+    };
+    let mut otherwise = None;
+    if end.keyword == "else" {
+        let (nodes, endfor) = parse_nodes(tokens, &["endfor"])?;
+        otherwise = Some((end.source, nodes));
+        end = endfor.ok_or_else(|| unclosed("for", line))?;
+    }
+
+    Ok(Node::For(Box::new(ForLoop { tag, header, line, body, otherwise, end: end.source })))
+}
+
+fn unclosed(tag: &str, line: usize) -> TemplateError {
+    TemplateError { line, message: format!("unclosed {{% {} %}} block", tag) }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Var(String),
+    Literal(Value),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare { left: Box<Expr>, right: Box<Expr>, equal: bool },
+    Filter { value: Box<Expr>, name: String, args: Vec<Expr> },
+}
+
+impl Expr {
+    fn collect_variables(&self, names: &mut Vec<String>) {
+        match self {
+            Expr::Var(name) if !names.contains(name) => names.push(name.clone()),
+            Expr::Var(_) | Expr::Literal(_) => {}
+            Expr::Not(inner) => inner.collect_variables(names),
+            Expr::And(left, right) | Expr::Or(left, right) | Expr::Compare { left, right, .. } => {
+                left.collect_variables(names);
+                right.collect_variables(names);
+            }
+            Expr::Filter { value, args, .. } => {
+                value.collect_variables(names);
+                args.iter().for_each(|arg| arg.collect_variables(names));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    Pipe,
+    LParen,
+    RParen,
+    Comma,
+    Eq,
+    Ne,
+}
+
+fn lex_expression(source: &str) -> Result<Vec<Tok>, String> {
+    let mut toks = vec![];
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '|' => {
+                chars.next();
+                toks.push(Tok::Pipe);
+            }
+            '(' => {
+                chars.next();
+                toks.push(Tok::LParen);
+            }
+            ')' => {
+                chars.next();
+                toks.push(Tok::RParen);
+            }
+            ',' => {
+                chars.next();
+                toks.push(Tok::Comma);
+            }
+            '=' | '!' => {
+                chars.next();
+                if chars.next() != Some('=') {
+                    return Err(format!("unexpected '{}'", c));
+                }
+                toks.push(if c == '=' { Tok::Eq } else { Tok::Ne });
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => value.extend(chars.next()),
+                        Some(ch) if ch == c => break,
+                        Some(ch) => value.push(ch),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                toks.push(Tok::Str(value));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = String::from(c);
+                chars.next();
+                while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    number.push(d);
+                    chars.next();
+                }
+                toks.push(Tok::Int(
+                    number.parse().map_err(|_| format!("invalid number '{}'", number))?,
+                ));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&ch) =
+                    chars.peek().filter(|ch| ch.is_alphanumeric() || **ch == '_' || **ch == '.')
+                {
+                    ident.push(ch);
+                    chars.next();
+                }
+                toks.push(Tok::Ident(ident));
+            }
+            _ => return Err(format!("unexpected '{}'", c)),
+        }
+    }
+
+    Ok(toks)
+}
+
+fn parse_expression(source: &str) -> Result<Expr, String> {
+    let toks = lex_expression(source)?;
+    if toks.is_empty() {
+        return Err("empty expression".to_string());
+    }
+    let mut parser = ExprParser { toks, pos: 0 };
+    let expr = parser.parse_or()?;
+    match parser.toks.get(parser.pos) {
+        Some(tok) => Err(format!("unexpected {:?}", tok)),
+        None => Ok(expr),
+    }
+}
+
+/// Recursive descent over `or` < `and` < `not` < `==` / `!=` < filters < literals.
+struct ExprParser {
+    toks: Vec<Tok>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let tok = self.toks.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Tok::Ident(ident)) if ident == keyword) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("or") {
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        while self.eat_keyword("and") {
+            left = Expr::And(Box::new(left), Box::new(self.parse_not()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_compare()
+    }
+
+    fn parse_compare(&mut self) -> Result<Expr, String> {
+        let left = self.parse_filtered()?;
+        let equal = match self.peek() {
+            Some(Tok::Eq) => true,
+            Some(Tok::Ne) => false,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let right = self.parse_filtered()?;
+        Ok(Expr::Compare { left: Box::new(left), right: Box::new(right), equal })
+    }
+
+    fn parse_filtered(&mut self) -> Result<Expr, String> {
+        let mut value = self.parse_primary()?;
+        while self.peek() == Some(&Tok::Pipe) {
+            self.pos += 1;
+            let Some(Tok::Ident(name)) = self.next() else {
+                return Err("expected a filter name after '|'".to_string());
+            };
+            let mut args = vec![];
+            if self.peek() == Some(&Tok::LParen) {
+                self.pos += 1;
+                while self.peek() != Some(&Tok::RParen) {
+                    args.push(self.parse_or()?);
+                    if self.peek() == Some(&Tok::Comma) {
+                        self.pos += 1;
+                    } else if self.peek() != Some(&Tok::RParen) {
+                        return Err(format!("expected ')' after arguments of '{}'", name));
+                    }
+                }
+                self.pos += 1;
+            }
+            value = Expr::Filter { value: Box::new(value), name, args };
+        }
+        Ok(value)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Tok::Str(value)) => Ok(Expr::Literal(Value::Str(value))),
+            Some(Tok::Int(value)) => Ok(Expr::Literal(Value::Int(value))),
+            Some(Tok::Ident(ident)) => Ok(match ident.as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                _ => Expr::Var(ident),
+            }),
+            Some(Tok::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Tok::RParen) => Ok(expr),
+                    _ => Err("expected ')'".to_string()),
+                }
+            }
+            Some(tok) => Err(format!("unexpected {:?}", tok)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+/// Template variables plus the loop variables of the enclosing `{% for %}` blocks.
+///
+/// A local without a value hides a template variable of the same name, like the loop variable
+/// of a `for` block that is passed through.
+struct Scope<'a> {
+    context: &'a Context,
+    locals: Vec<(String, Option<Value>)>,
+}
+
+impl Scope<'_> {
+    fn lookup(&self, name: &str) -> Option<&Value> {
+        match self.locals.iter().rev().find(|(local, _)| local == name) {
+            Some((_, value)) => value.as_ref(),
+            None => self.context.get(name),
+        }
+    }
+
+    /// Whether every variable of the expression is known, blocks using unknown ones belong to
+    /// another template language.
+    fn is_known(&self, expr: &Expr) -> bool {
+        self.first_unknown(expr).is_none()
+    }
+
+    fn first_unknown<'e>(&self, expr: &'e Expr) -> Option<&'e str> {
+        match expr {
+            Expr::Var(name) => self.lookup(name).is_none().then_some(name.as_str()),
+            Expr::Literal(_) => None,
+            Expr::Not(inner) => self.first_unknown(inner),
+            Expr::And(left, right) | Expr::Or(left, right) | Expr::Compare { left, right, .. } => {
+                self.first_unknown(left).or_else(|| self.first_unknown(right))
+            }
+            Expr::Filter { value, args, .. } => self
+                .first_unknown(value)
+                .or_else(|| args.iter().find_map(|arg| self.first_unknown(arg))),
+        }
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<Value, String> {
+        Ok(match expr {
+            Expr::Var(name) => {
+                self.lookup(name).cloned().ok_or_else(|| format!("unknown variable '{}'", name))?
+            }
+            Expr::Literal(value) => value.clone(),
+            Expr::Not(inner) => Value::Bool(!self.eval(inner)?.is_truthy()),
+            Expr::And(left, right) => {
+                Value::Bool(self.eval(left)?.is_truthy() && self.eval(right)?.is_truthy())
+            }
+            Expr::Or(left, right) => {
+                Value::Bool(self.eval(left)?.is_truthy() || self.eval(right)?.is_truthy())
+            }
+            Expr::Compare { left, right, equal } => {
+                let same = self.eval(left)?.to_string() == self.eval(right)?.to_string();
+                Value::Bool(same == *equal)
+            }
+            Expr::Filter { value, name, args } => {
+                let value = self.eval(value)?;
+                let args = args.iter().map(|arg| self.eval(arg)).collect::<Result<Vec<_>, _>>()?;
+                apply_filter(name, value, &args)?
+            }
+        })
+    }
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    scope: &mut Scope,
+    output: &mut String,
+) -> Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Output { expr, source, line } => {
+                if !scope.is_known(expr) {
+                    output.push_str(source);
+                    continue;
+                }
+                let value =
+                    scope.eval(expr).map_err(|message| TemplateError { line: *line, message })?;
+                output.push_str(&value.to_string());
+            }
+            Node::If { branches, otherwise, end } => {
+                let conditions: Option<Vec<&Expr>> = branches
+                    .iter()
+                    .map(|branch| branch.condition.as_ref().filter(|expr| scope.is_known(expr)))
+                    .collect();
+                let Some(conditions) = conditions else {
+                    let sections = branches.iter().map(|branch| (&branch.tag, &branch.body));
+                    let sections = sections.chain(otherwise.iter().map(|(tag, body)| (tag, body)));
+                    render_passed_through(sections, end, scope, output)?;
+                    continue;
+                };
+
+                let mut body = otherwise.as_ref().map_or(&[][..], |(_, body)| body);
+                for (branch, condition) in branches.iter().zip(conditions) {
+                    if eval_tag(scope, condition, branch.line)?.is_truthy() {
+                        body = &branch.body;
+                        break;
+                    }
+                }
+                render_nodes(body, scope, output)?;
+            }
+            Node::For(for_loop) => render_for(for_loop, scope, output)?,
+        }
+    }
+    Ok(())
+}
+
+fn render_for(
+    for_loop: &ForLoop,
+    scope: &mut Scope,
+    output: &mut String,
+) -> Result<(), TemplateError> {
+    let ForLoop { tag, header, line, body, otherwise, end } = for_loop;
+
+    let Some((name, iterable)) = header.as_ref().filter(|(_, expr)| scope.is_known(expr)) else {
+        // Hide the loop variable so its tags are kept like the rest of the block
+        let depth = scope.locals.len();
+        scope.locals.extend(header.iter().map(|(name, _)| (name.clone(), None)));
+        let sections = std::iter::once((tag, body));
+        let sections = sections.chain(otherwise.iter().map(|(tag, body)| (tag, body)));
+        let result = render_passed_through(sections, end, scope, output);
+        scope.locals.truncate(depth);
+        return result;
+    };
+
+    let items = eval_tag(scope, iterable, *line)?.items();
+    if items.is_empty() {
+        if let Some((_, body)) = otherwise {
+            render_nodes(body, scope, output)?;
+        }
+        return Ok(());
+    }
+
+    let count = items.len();
+    for (index, item) in items.into_iter().enumerate() {
+        scope.locals.push((name.clone(), Some(item)));
+        scope.locals.push(("loop.index".to_string(), Some(Value::Int(index as i64 + 1))));
+        scope.locals.push(("loop.first".to_string(), Some(Value::Bool(index == 0))));
+        scope.locals.push(("loop.last".to_string(), Some(Value::Bool(index + 1 == count))));
+        let result = render_nodes(body, scope, output);
+        scope.locals.truncate(scope.locals.len() - 4);
+        result?;
+    }
+    Ok(())
+}
+
+/// Renders a block of another template language: its tags are kept as they are and only the
+/// content between them is rendered.
+fn render_passed_through<'n>(
+    sections: impl Iterator<Item = (&'n String, &'n Vec<Node>)>,
+    end: &str,
+    scope: &mut Scope,
+    output: &mut String,
+) -> Result<(), TemplateError> {
+    for (tag, body) in sections {
+        output.push_str(tag);
+        render_nodes(body, scope, output)?;
+    }
+    output.push_str(end);
+    Ok(())
+}
+
+fn eval_tag(scope: &Scope, expr: &Expr, line: usize) -> Result<Value, TemplateError> {
+    scope.eval(expr).map_err(|message| TemplateError { line, message })
+}
+
+fn apply_filter(name: &str, value: Value, args: &[Value]) -> Result<Value, String> {
+    let expect_args = |count: usize| {
+        if args.len() == count {
+            Ok(())
+        } else {
+            Err(format!("filter '{}' expects {} argument(s), got {}", name, count, args.len()))
+        }
+    };
+    let text = value.to_string();
+
+    let result = match name {
+        "upper" => text.to_uppercase(),
+        "lower" => text.to_lowercase(),
+        "kebab_case" => words(&text).join("-").to_lowercase(),
+        "snake_case" => words(&text).join("_").to_lowercase(),
+        "pascal_case" => words(&text).iter().map(|word| capitalize(word)).collect(),
+        "camel_case" => words(&text)
+            .iter()
+            .enumerate()
+            .map(|(index, word)| if index == 0 { word.to_lowercase() } else { capitalize(word) })
+            .collect(),
+        "replace" => {
+            expect_args(2)?;
+            text.replace(&args[0].to_string(), &args[1].to_string())
+        }
+        "join" => {
+            let separator = args.first().map_or(", ".to_string(), Value::to_string);
+            let items: Vec<String> = value.items().iter().map(Value::to_string).collect();
+            items.join(&separator)
+        }
+        _ => return Err(format!("unknown filter '{}'", name)),
+    };
+    if !matches!(name, "replace" | "join") {
+        expect_args(0)?;
+    }
+    Ok(Value::Str(result))
+}
+
+/// Splits identifiers like `my-app`, `my_app`, `myApp` or `HTTPServer` into words.
+fn words(input: &str) -> Vec<String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut words = vec![];
+    let mut current = String::new();
+
+    for (index, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> Context {
+        Context::from([
+            ("name".to_string(), Value::Str("My Cool-App".to_string())),
+            ("use_ts".to_string(), Value::Str("true".to_string())),
+            ("docker".to_string(), Value::Bool(false)),
+            ("features".to_string(), Value::Str("router, store".to_string())),
+        ])
+    }
+
+    #[test]
+    fn test_render_filters() {
+        let source = "{{ name | kebab_case }} {{ name | snake_case }} {{ name | pascal_case }} \
+            {{ name | upper }} {{ name | replace(' ', '') }} {{ name | camel_case }}";
+        assert_eq!(
+            render(source, &context()).unwrap(),
+            "my-cool-app my_cool_app MyCoolApp MY COOL-APP MyCool-App myCoolApp"
+        );
+        assert_eq!(words("HTTPServer2Go"), vec!["HTTP", "Server2", "Go"]);
+    }
+
+    #[test]
+    fn test_render_conditionals_strip_tag_lines() {
+        let source = "\
+{% if use_ts %}
+const a: number = 1;
+{% elif docker %}
+docker
+{% else %}
+const a = 1;
+{% endif %}
+{% if not use_ts and docker == false %}js{% endif %}end";
+        assert_eq!(render(source, &context()).unwrap(), "const a: number = 1;\nend");
+
+        let js = Context::from([
+            ("use_ts".to_string(), Value::Str("false".to_string())),
+            ("docker".to_string(), Value::Bool(false)),
+        ]);
+        assert_eq!(render(source, &js).unwrap(), "const a = 1;\njsend");
+    }
+
+    #[test]
+    fn test_render_for_loop() {
+        let source =
+            "{% for f in features %}{{ f | upper }}{% if not loop.last %},{% endif %}{% endfor %}";
+        assert_eq!(render(source, &context()).unwrap(), "ROUTER,STORE");
+
+        let list = Context::from([("items".to_string(), Value::Str("a,b".into()))]);
+        let source = "{% for item in items %}\n- {{ loop.index }} {{ item }}\n{% endfor %}\n";
+        assert_eq!(render(source, &list).unwrap(), "- 1 a\n- 2 b\n");
+
+        let empty = Context::from([("items".to_string(), Value::Str(String::new()))]);
+        let source = "{% for item in items %}{{ item }}{% else %}none{% endfor %}";
+        assert_eq!(render(source, &empty).unwrap(), "none");
+    }
+
+    #[test]
+    fn test_render_keeps_foreign_syntax() {
+        let source = "run: ${{ github.ref }} {{ unknown }} {{ {a: 1} }} {% include x.html %}\n\
+            {# note #}{% raw %}{{ name }}{% endraw %} ${#items[@]} {{ name | upper }}";
+        assert_eq!(
+            render(source, &context()).unwrap(),
+            "run: ${{ github.ref }} {{ unknown }} {{ {a: 1} }} {% include x.html %}\n\
+            {{ name }} ${#items[@]} MY COOL-APP"
+        );
+    }
+
+    #[test]
+    fn test_render_keeps_foreign_blocks() {
+        let source = "\
+{% extends \"base.html\" %}
+{% block content %}
+  {% if user.is_authenticated %}
+  <p>{{ user.name }} in {{ name | upper }}</p>
+  {% elif items|length > 0 %}
+  {% else %}
+  {% endif %}
+{% for name in users %}{{ name }}{% empty %}-{% endfor %}
+{% for key, value in data.items() %}{{ key }}{% else %}none{% endfor %}
+{% endblock %}
+";
+        let expected = source.replace("{{ name | upper }}", "MY COOL-APP");
+        assert_eq!(render(source, &context()).unwrap(), expected);
+
+        let source = "{% if docker %}a{% elif user.admin %}b{% endif %}{% if docker %}c{% endif %}";
+        assert_eq!(
+            render(source, &context()).unwrap(),
+            "{% if docker %}a{% elif user.admin %}b{% endif %}"
+        );
+    }
+
+    #[test]
+    fn test_evaluate_expression() {
+        assert_eq!(evaluate("docker == true", &context()).unwrap(), Value::Bool(false));
+        assert_eq!(evaluate("use_ts == 'true'", &context()).unwrap(), Value::Bool(true));
+        assert_eq!(
+            evaluate("name | snake_case", &context()).unwrap(),
+            Value::Str("my_cool_app".into())
+        );
+        assert!(evaluate("docker ==", &context()).is_err());
+        assert!(evaluate("missing", &context()).is_err());
+    }
+
+    #[test]
+    fn test_expression_variables() {
+        assert_eq!(
+            variables("not docker and (ci or name | replace(sep, '-') == docker)").unwrap(),
+            vec!["docker", "ci", "name", "sep"]
+        );
+        assert!(variables("docker ==").is_err());
+    }
+
+    #[test]
+    fn test_render_errors_report_line() {
+        let err = render("a\n{% if use_ts %}\nb\n", &context()).unwrap_err();
+        assert_eq!(err, TemplateError { line: 2, message: "unclosed {% if %} block".into() });
+
+        let err = render("a\nb\n{% if name | shout %}x{% endif %}", &context()).unwrap_err();
+        assert_eq!(err.to_string(), "line 3: unknown filter 'shout'");
+
+        let err = render("\n{{ name | shout }}", &context()).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: unknown filter 'shout'");
+
+        let err = render("{% endfor %}", &context()).unwrap_err();
+        assert_eq!(err.to_string(), "line 1: unexpected {% endfor %}");
+
+        let err = render("{{ name | replace('a') }}", &context()).unwrap_err();
+        assert!(err.message.contains("expects 2 argument(s)"), "{}", err);
+    }
+}
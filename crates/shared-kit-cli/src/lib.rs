@@ -6,12 +6,13 @@ pub mod config;
 pub mod constant;
 pub mod subcommand;
 
+mod cli;
 mod components;
 mod utils;
-mod cli;
 
 pub fn shared_kit_cli() {
     if let Err(e) = run_cli() {
-        error_msg!("{}", e.to_string())
+        error_msg!("{}", e.to_string());
+        std::process::exit(1);
     }
 }
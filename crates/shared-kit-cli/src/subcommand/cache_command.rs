@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use clap::{Args, Subcommand};
+use console::style;
+use indicatif::{HumanBytes, HumanDuration};
+
+use crate::helper::cache::RepoCache;
+
+#[derive(Args, Debug)]
+pub struct CacheCommand {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// List cached repository archives
+    List,
+    /// Remove every cached archive
+    Clean,
+    /// Remove archives that have not been fetched for a while
+    Prune(CachePruneArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct CachePruneArgs {
+    /// Remove archives last fetched more than this many days ago
+    #[arg(long = "older-than", value_name = "DAYS", default_value_t = 30)]
+    pub older_than: u64,
+}
+
+pub fn cache_command_action(args: &CacheCommand) -> anyhow::Result<()> {
+    let cache = RepoCache::open_default()?;
+
+    match &args.action {
+        CacheAction::List => list_cache(&cache),
+        CacheAction::Clean => {
+            let removed = cache.clean()?;
+            info_msg!("🧹 Removed {} cached archive(s) from '{}'", removed, cache.root().display());
+            Ok(())
+        }
+        CacheAction::Prune(prune) => {
+            let removed = cache.prune(prune.older_than.saturating_mul(24 * 60 * 60))?;
+            info_msg!(
+                "🧹 Removed {} cached archive(s) older than {} day(s)",
+                removed,
+                prune.older_than
+            );
+            Ok(())
+        }
+    }
+}
+
+fn list_cache(cache: &RepoCache) -> anyhow::Result<()> {
+    let entries = cache.entries()?;
+
+    if entries.is_empty() {
+        warn_msg!("Download cache is empty: '{}'", cache.root().display());
+        return Ok(());
+    }
+
+    println!("{}", style(cache.root().display()).dim());
+    for entry in entries {
+        let age = entry
+            .read_meta()
+            .map(|meta| format!("{} ago", HumanDuration(Duration::from_secs(meta.age()))))
+            .unwrap_or_else(|| "incomplete".to_string());
+        println!(
+            "{:<56} {:>10} {}",
+            style(&entry.key).green().bold(),
+            HumanBytes(entry.size()).to_string(),
+            style(age).dim()
+        );
+    }
+
+    Ok(())
+}
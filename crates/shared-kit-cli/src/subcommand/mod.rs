@@ -1 +1,3 @@
+pub mod cache_command;
 pub mod new_command;
+pub mod template_command;
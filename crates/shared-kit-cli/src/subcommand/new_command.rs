@@ -1,16 +1,26 @@
 use anyhow::{Context, Ok};
 use clap::Args;
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::components::new_command::{ensure_target_directory, ensure_template_selected};
+use crate::components::new_command::{
+    TargetDir, check_project_name, conditional_excludes, ensure_target_directory,
+    ensure_template_selected, ensure_template_vars,
+};
 use crate::components::progress::copy_directory_with_progress;
 use crate::config::Config;
-use crate::constant::TemplateKind;
-use crate::helper::path::expand_dir;
-use crate::helper::repo::resolve_repo_to_dir;
+use crate::constant::{OnExistsAction, TemplateItem, TemplateKind, TemplateVarValues};
+use crate::helper::file_system::NameRenderer;
+use crate::helper::file_transform_pipe::{
+    render_template_middleware, replace_template_vars_middleware, template_name_renderer,
+};
+use crate::helper::hooks::run_completed_script;
+use crate::helper::path::{expand_dir, join_with_config_dir};
+use crate::helper::path_matcher::{PathFilter, PathMatcher};
+use crate::helper::repo::{FetchOptions, resolve_repo_to_dir};
 
-#[derive(Args, Debug)]
+#[derive(Args, Debug, Default)]
 pub struct NewCommand {
     /// Name of the new project
     pub name: String,
@@ -30,6 +40,42 @@ pub struct NewCommand {
     /// Custom config file path (default: /home/(user)/.config/shared-kit-cli/new-config.toml)
     #[arg(short = 'c', long = "config", value_name = "CONFIG")]
     pub config: Option<String>,
+
+    /// Name of a configured template, skips the interactive selection
+    #[arg(short = 'n', long = "template-name", value_name = "NAME")]
+    pub template_name: Option<String>,
+
+    /// Template variable value, can be repeated (e.g. --var project_name=demo)
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// File with template variable answers (toml, json or yaml)
+    #[arg(long = "answers", value_name = "FILE")]
+    pub answers: Option<String>,
+
+    /// What to do when the target directory already exists (overwrite, fail, merge)
+    #[arg(long = "on-exists", value_name = "ACTION")]
+    pub on_exists: Option<OnExistsAction>,
+
+    /// Use default values for template variables without prompting
+    #[arg(short = 'y', long = "yes")]
+    pub yes: bool,
+
+    /// Only use the download cache for remote templates
+    #[arg(long = "offline")]
+    pub offline: bool,
+
+    /// Expected SHA-256 of the repo archive, overrides the template's sha256
+    #[arg(long = "checksum", value_name = "SHA256")]
+    pub checksum: Option<String>,
+}
+
+fn parse_var(input: &str) -> Result<(String, String), String> {
+    input
+        .split_once('=')
+        .filter(|(key, _)| !key.trim().is_empty())
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("invalid variable '{}', expected KEY=VALUE", input))
 }
 
 pub fn new_command_action(config: &mut Config, args: &NewCommand) -> anyhow::Result<()> {
@@ -37,39 +83,62 @@ pub fn new_command_action(config: &mut Config, args: &NewCommand) -> anyhow::Res
         config.reload(Some(cfg.clone()))?;
     }
 
-    let mut target = env::current_dir()?.join(&args.name);
-    target = ensure_target_directory(target)?;
+    check_project_name(&args.name)?;
+    let target = env::current_dir()?.join(&args.name);
+    let target = ensure_target_directory(target, args.on_exists.as_ref())?;
+
+    info_msg!("📁 Project will be created in: '{}'", target.path.display());
 
-    info_msg!("📁 Project will be created in: '{}'", target.display());
+    let direct_template = TemplateItem::default();
 
-    if try_apply_direct_template(&target, args.template.clone())? {
+    if try_apply_direct_template(&target, args.template.clone(), None, &direct_template, args)? {
         return Ok(());
     }
 
-    if try_apply_direct_repo(&target, args.repo.clone())? {
+    let fetch = config.metadata.fetch_options(args.offline);
+
+    if try_apply_direct_repo(&target, args.repo.clone(), &direct_template, &fetch, args)? {
         return Ok(());
     }
 
-    let new_template = ensure_template_selected(&config, args)?;
+    let (template_name, new_template) = ensure_template_selected(config, args)?;
+    let config_path = config.template_source(&template_name);
 
-    if try_apply_direct_template(&target, new_template.template.clone())? {
+    if try_apply_direct_template(
+        &target,
+        new_template.template.clone(),
+        config_path,
+        &new_template,
+        args,
+    )? {
         return Ok(());
     }
 
-    if try_apply_direct_repo(&target, new_template.repo.clone())? {
+    if try_apply_direct_repo(&target, new_template.repo.clone(), &new_template, &fetch, args)? {
         return Ok(());
     }
 
     Ok(())
 }
 
-fn try_apply_direct_template(target: &PathBuf, template: Option<String>) -> anyhow::Result<bool> {
+/// Applies a local template directory.
+///
+/// Relative paths are resolved against `config_path`'s directory when the template comes from a
+/// config file, and against the current directory when `config_path` is `None`.
+fn try_apply_direct_template(
+    target: &TargetDir,
+    template: Option<String>,
+    config_path: Option<&PathBuf>,
+    item: &TemplateItem,
+    args: &NewCommand,
+) -> anyhow::Result<bool> {
     if template.is_none() {
         return Ok(false);
     }
     let template_path = template.unwrap();
     let path = expand_dir(&template_path)
         .with_context(|| format!("Failed to expand template path: {}", template_path))?;
+    let path = join_with_config_dir(config_path, &path);
 
     if !path.exists() {
         anyhow::bail!(
@@ -78,21 +147,118 @@ fn try_apply_direct_template(target: &PathBuf, template: Option<String>) -> anyh
         );
     }
 
-    copy_directory_with_progress(&path, &target)?;
+    generate_from_template(&path, target, item, args)?;
 
     Ok(true)
 }
 
-fn try_apply_direct_repo(target: &PathBuf, repo: Option<String>) -> anyhow::Result<bool> {
+fn try_apply_direct_repo(
+    target: &TargetDir,
+    repo: Option<String>,
+    item: &TemplateItem,
+    fetch: &FetchOptions,
+    args: &NewCommand,
+) -> anyhow::Result<bool> {
     if repo.is_none() {
         return Ok(false);
     }
 
     let repo_url = repo.unwrap();
 
-    let repo = resolve_repo_to_dir(&repo_url)?;
+    let checksum = args.checksum.as_deref().or(item.sha256.as_deref());
+    let repo = resolve_repo_to_dir(&repo_url, item.repo_subdir.as_deref(), checksum, fetch)?;
 
-    copy_directory_with_progress(&repo.root_dir, target)?;
+    generate_from_template(&repo.root_dir, target, item, args)?;
 
     Ok(true)
 }
+
+fn generate_from_template(
+    origin: &Path,
+    target: &TargetDir,
+    item: &TemplateItem,
+    args: &NewCommand,
+) -> anyhow::Result<()> {
+    let values = if item.template_vars.is_empty() {
+        TemplateVarValues::new()
+    } else {
+        ensure_template_vars(&item.template_vars, args)?
+    };
+
+    let mut excludes = item.excludes.clone();
+    excludes.extend(conditional_excludes(item, &values)?);
+    let filter = PathFilter::new(&item.includes, &excludes)
+        .with_context(|| "Failed to parse template includes/excludes/conditional_paths")?;
+    let filter = if filter.is_empty() { None } else { Some(filter) };
+    let binary = PathMatcher::new(&item.binary)
+        .with_context(|| "Failed to parse template binary patterns")?;
+    let binary = if binary.is_empty() { None } else { Some(binary) };
+
+    let mut middlewares = vec![];
+    let mut render_name = None;
+
+    if !item.template_vars.is_empty() {
+        middlewares.push(render_template_middleware(
+            item.template_vars.clone(),
+            values.clone(),
+            origin.to_path_buf(),
+        )?);
+        middlewares.push(replace_template_vars_middleware(
+            item.template_vars.clone(),
+            values.clone(),
+            origin.to_path_buf(),
+        )?);
+        render_name = Some(template_name_renderer(item.template_vars.clone(), values));
+    }
+
+    let render_name = render_name.as_ref().map(|render| render as &NameRenderer);
+    let backup = if target.replace { Some(move_aside(&target.path)?) } else { None };
+    let copied = copy_directory_with_progress(
+        origin,
+        &target.path,
+        filter,
+        binary,
+        middlewares,
+        render_name,
+    );
+
+    match (copied, backup) {
+        (Err(err), Some(backup)) => {
+            restore_backup(&backup, &target.path)?;
+            return Err(err);
+        }
+        (copied, Some(backup)) => {
+            copied?;
+            fs::remove_dir_all(&backup)
+                .with_context(|| format!("Failed to remove directory: {}", backup.display()))?;
+        }
+        (copied, None) => copied?,
+    }
+
+    if !item.completed_script.is_empty() {
+        run_completed_script(&item.completed_script, &target.path)?;
+    }
+
+    Ok(())
+}
+
+/// Moves a directory that is about to be overwritten out of the way, so it can be restored
+/// when generating the new project fails.
+fn move_aside(path: &Path) -> anyhow::Result<PathBuf> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let backup = path.with_file_name(format!(".{}.shared-kit-backup-{}", name, std::process::id()));
+
+    fs::rename(path, &backup)
+        .with_context(|| format!("Failed to move '{}' out of the way", path.display()))?;
+    Ok(backup)
+}
+
+fn restore_backup(backup: &Path, path: &Path) -> anyhow::Result<()> {
+    if path.exists() {
+        fs::remove_dir_all(path)
+            .with_context(|| format!("Failed to remove directory: {}", path.display()))?;
+    }
+    fs::rename(backup, path).with_context(|| {
+        format!("Failed to restore '{}' from '{}'", path.display(), backup.display())
+    })
+}
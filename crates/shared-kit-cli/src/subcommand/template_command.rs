@@ -0,0 +1,278 @@
+use anyhow::Context;
+use clap::{Args, Subcommand};
+use console::style;
+use path_clean::PathClean;
+use std::env;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::constant::{TemplateItem, TemplateKind};
+use crate::helper::path::expand_dir;
+use crate::helper::repo::{GitRef, parse_repo_input, resolve_repo_to_dir};
+
+#[derive(Args, Debug)]
+pub struct TemplateCommand {
+    #[command(subcommand)]
+    pub action: TemplateAction,
+
+    /// Custom config file path, used as the active config file
+    #[arg(
+        id = "template_config",
+        short = 'c',
+        long = "config",
+        value_name = "CONFIG",
+        global = true
+    )]
+    pub config: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplateAction {
+    /// List configured templates
+    List(TemplateListArgs),
+    /// Show the full configuration of a template
+    Show(TemplateNameArgs),
+    /// Add a template to the active config file
+    Add(TemplateAddArgs),
+    /// Remove a template from the config file that defines it
+    Remove(TemplateNameArgs),
+    /// Record the sha256 of a remote template's archive in its config file
+    Pin(TemplateNameArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct TemplateListArgs {
+    /// Only list templates of this kind (project, monorepo, package)
+    #[arg(short = 'k', long = "kind", value_name = "KIND")]
+    pub kind: Option<TemplateKind>,
+}
+
+#[derive(Args, Debug)]
+pub struct TemplateNameArgs {
+    /// Name of the template
+    pub name: String,
+}
+
+#[derive(Args, Debug)]
+#[command(group = clap::ArgGroup::new("source").required(true).args(["path", "repo"]))]
+pub struct TemplateAddArgs {
+    /// Name of the template
+    pub name: String,
+
+    /// Local template directory
+    #[arg(short = 'p', long = "path", value_name = "PATH")]
+    pub path: Option<String>,
+
+    /// Remote repository address
+    #[arg(short = 'r', long = "repo", value_name = "REPO")]
+    pub repo: Option<String>,
+
+    /// Kind of the template (project, monorepo, package), defaults to package
+    #[arg(short = 'k', long = "kind", value_name = "KIND")]
+    pub kind: Option<TemplateKind>,
+
+    /// Replace an existing template with the same name
+    #[arg(short = 'f', long = "force")]
+    pub force: bool,
+}
+
+pub fn template_command_action(config: &mut Config, args: &TemplateCommand) -> anyhow::Result<()> {
+    if let Some(cfg) = &args.config {
+        config.reload(Some(cfg.clone()))?;
+    }
+
+    match &args.action {
+        TemplateAction::List(list) => list_templates(config, list),
+        TemplateAction::Show(show) => show_template(config, &show.name),
+        TemplateAction::Add(add) => add_template(config, add),
+        TemplateAction::Remove(remove) => {
+            let path = config.remove_template(&remove.name)?;
+            info_msg!("🗑️ Removed template '{}' from '{}'", remove.name, path.display());
+            Ok(())
+        }
+        TemplateAction::Pin(pin) => pin_template(config, &pin.name),
+    }
+}
+
+fn list_templates(config: &Config, args: &TemplateListArgs) -> anyhow::Result<()> {
+    let templates = config.metadata.get_templates(args.kind.clone());
+
+    if templates.is_empty() {
+        warn_msg!("No templates found in config.");
+        return Ok(());
+    }
+
+    let mut names: Vec<&String> = templates.keys().collect();
+    names.sort();
+
+    for name in names {
+        let item = &templates[name];
+        println!(
+            "{:<24} {:<10} {}",
+            style(name).green().bold(),
+            format!("{:?}", item.kind).to_lowercase(),
+            style(template_source_label(item)).dim()
+        );
+    }
+
+    Ok(())
+}
+
+fn show_template(config: &Config, name: &str) -> anyhow::Result<()> {
+    let item = config
+        .metadata
+        .templates
+        .get(name)
+        .with_context(|| format!("Template '{}' not found in config", name))?;
+
+    let config_file = config
+        .template_source(name)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "<none>".to_string());
+
+    println!("{}", style(name).green().bold());
+    println!("  config:   {}", config_file);
+    println!("  kind:     {}", format!("{:?}", item.kind).to_lowercase());
+    println!("  source:   {}", template_source_label(item));
+    if let Some(subdir) = &item.repo_subdir {
+        println!("  subdir:   {}", subdir);
+    }
+    if let Some(sha256) = &item.sha256 {
+        println!("  sha256:   {}", sha256);
+    }
+    print_list("includes", &item.includes);
+    print_list("excludes", &item.excludes);
+    print_list("binary", &item.binary);
+    print_list("completed_script", &item.completed_script);
+
+    if !item.template_vars.is_empty() {
+        println!("  template_vars:");
+        for var in &item.template_vars {
+            println!("    - {}", style(&var.placeholder).cyan());
+            if !var.var_type.is_string() {
+                println!("      type:    {}", var.var_type);
+            }
+            if !var.choices.is_empty() {
+                println!("      choices: {}", var.choices.join(", "));
+            }
+            if let Some(prompt) = &var.prompt {
+                println!("      prompt:  {}", prompt);
+            }
+            if let Some(default) = &var.default {
+                println!("      default: {}", default);
+            }
+            if !var.includes_paths.is_empty() {
+                println!("      includes_paths: {}", var.includes_paths.join(", "));
+            }
+            if !var.excludes_paths.is_empty() {
+                println!("      excludes_paths: {}", var.excludes_paths.join(", "));
+            }
+            if let Some(validate) = &var.validate {
+                println!("      validate: {}", validate);
+            }
+            if let Some(when) = &var.when {
+                println!("      when:    {}", when);
+            }
+            if let Some(compute) = &var.compute {
+                println!("      compute: {}", compute);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn add_template(config: &mut Config, args: &TemplateAddArgs) -> anyhow::Result<()> {
+    if config.metadata.templates.contains_key(&args.name) && !args.force {
+        anyhow::bail!("Template '{}' already exists. Use --force to replace it.", args.name);
+    }
+
+    let template = match &args.path {
+        Some(path) => Some(resolve_template_path(config, path)?),
+        None => None,
+    };
+
+    let item = TemplateItem {
+        kind: args.kind.clone().unwrap_or_default(),
+        template,
+        repo: args.repo.clone(),
+        ..Default::default()
+    };
+
+    let path = config.save_template(&args.name, item)?;
+    info_msg!("✅ Added template '{}' to '{}'", args.name, path.display());
+
+    Ok(())
+}
+
+/// Downloads a remote template and stores the archive's sha256 next to its `repo`.
+fn pin_template(config: &mut Config, name: &str) -> anyhow::Result<()> {
+    let mut item = config
+        .metadata
+        .templates
+        .get(name)
+        .cloned()
+        .with_context(|| format!("Template '{}' not found in config", name))?;
+    let repo = item.repo.clone().with_context(|| {
+        format!("❌ Template '{}' has no repo, only remote templates can be pinned", name)
+    })?;
+
+    let repo_info = parse_repo_input(&repo, &config.metadata.hosts)?;
+    if matches!(repo_info.r#ref, GitRef::Default | GitRef::Branch(_)) {
+        warn_msg!(
+            "'{}' follows a branch, its checksum changes with every new commit. Pin a tag or commit for a stable checksum.",
+            repo
+        );
+    }
+
+    let fetch = config.metadata.fetch_options(false);
+    let fetched = resolve_repo_to_dir(&repo, item.repo_subdir.as_deref(), None, &fetch)?;
+    let sha256 = fetched.sha256.with_context(|| {
+        format!("❌ '{}' is fetched with git clone, only archive downloads can be pinned", repo)
+    })?;
+
+    item.sha256 = Some(sha256.clone());
+    let path = config.update_template(name, item)?;
+    info_msg!("📌 Pinned '{}' to sha256 {} in '{}'", name, sha256, path.display());
+
+    Ok(())
+}
+
+/// Makes a command line path usable from the config file: relative to the config directory when
+/// the template lives below it, absolute otherwise.
+fn resolve_template_path(config: &Config, path: &str) -> anyhow::Result<String> {
+    if path.starts_with("~/") {
+        return Ok(path.to_string());
+    }
+
+    let expanded =
+        expand_dir(path).with_context(|| format!("Failed to expand template path: {}", path))?;
+    let absolute: PathBuf = env::current_dir()?.join(expanded).clean();
+
+    if !absolute.is_dir() {
+        anyhow::bail!("❌ Template path is not a directory: '{}'", absolute.display());
+    }
+
+    // A bare `-c shared-kit.json` has an empty parent, so compare against the absolute path
+    let config_path = config.current_config_path.as_deref().map(std::path::absolute).transpose()?;
+    let config_dir = config_path.as_deref().and_then(|p| p.parent()).map(|dir| dir.clean());
+    let relative = config_dir
+        .and_then(|dir| absolute.strip_prefix(dir).ok().map(|rel| rel.to_path_buf()))
+        .map(|rel| format!("./{}", rel.display()));
+
+    Ok(relative.unwrap_or_else(|| absolute.display().to_string()))
+}
+
+fn template_source_label(item: &TemplateItem) -> String {
+    match (&item.template, &item.repo) {
+        (Some(template), _) => format!("template: {}", template),
+        (None, Some(repo)) => format!("repo: {}", repo),
+        (None, None) => "<no source>".to_string(),
+    }
+}
+
+fn print_list(label: &str, items: &[String]) {
+    if !items.is_empty() {
+        println!("  {}: {}", label, items.join(", "));
+    }
+}
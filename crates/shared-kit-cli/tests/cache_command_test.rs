@@ -0,0 +1,223 @@
+use predicates::prelude::*;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tempfile::tempdir;
+use zip::write::{ExtendedFileOptions, FileOptions};
+
+fn shared_kit(cache_dir: &Path) -> assert_cmd::Command {
+    let mut cmd = assert_cmd::Command::cargo_bin("shared-kit").unwrap();
+    cmd.env("SHARED_KIT_CACHE_DIR", cache_dir);
+    cmd
+}
+
+/// Seeds the cache the same way a download of `octocat/hello` at `fetched_at` would have.
+fn seed_hello_archive(cache_dir: &Path, fetched_at: u64) {
+    let entry = cache_dir.join("github/octocat/hello/default");
+    fs::create_dir_all(&entry).unwrap();
+
+    let mut zip = zip::ZipWriter::new(fs::File::create(entry.join("archive.zip")).unwrap());
+    let options: FileOptions<'_, ExtendedFileOptions> = FileOptions::default();
+    zip.start_file("hello-main/README.md", options).unwrap();
+    zip.write_all(b"# hello").unwrap();
+    zip.finish().unwrap();
+
+    let meta = format!(
+        r#"{{ "url": "https://github.com/octocat/hello/archive/refs/heads/main.zip", "fetched_at": {} }}"#,
+        fetched_at
+    );
+    fs::write(entry.join("meta.json"), meta).unwrap();
+}
+
+#[test]
+fn test_new_offline_uses_cached_archive() {
+    let temp = tempdir().unwrap();
+    let cache_dir = temp.path().join("cache");
+    seed_hello_archive(&cache_dir, 0);
+
+    shared_kit(&cache_dir)
+        .current_dir(temp.path())
+        .args(["new", "app", "--repo", "octocat/hello", "--offline"])
+        .assert()
+        .success();
+
+    assert_eq!(fs::read_to_string(temp.path().join("app/README.md")).unwrap(), "# hello");
+}
+
+#[test]
+fn test_new_offline_without_cache_should_fail() {
+    let temp = tempdir().unwrap();
+
+    shared_kit(&temp.path().join("cache"))
+        .current_dir(temp.path())
+        .args(["new", "app", "--repo", "octocat/missing", "--offline"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not in the download cache"));
+}
+
+#[test]
+fn test_cache_list_prune_clean() {
+    let temp = tempdir().unwrap();
+    let cache_dir = temp.path().join("cache");
+    seed_hello_archive(&cache_dir, 0);
+
+    shared_kit(&cache_dir)
+        .args(["cache", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("github/octocat/hello/default"));
+
+    shared_kit(&cache_dir)
+        .args(["cache", "prune", "--older-than", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 1 cached archive(s)"));
+
+    seed_hello_archive(&cache_dir, 0);
+    shared_kit(&cache_dir).args(["cache", "clean"]).assert().success();
+
+    shared_kit(&cache_dir)
+        .args(["cache", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Download cache is empty"));
+}
+
+#[test]
+fn test_cache_clean_keeps_foreign_files() {
+    let temp = tempdir().unwrap();
+    let cache_dir = temp.path().join("cache");
+    seed_hello_archive(&cache_dir, 0);
+    fs::create_dir_all(cache_dir.join("unrelated")).unwrap();
+    fs::write(cache_dir.join("unrelated/important.txt"), "keep").unwrap();
+
+    shared_kit(&cache_dir)
+        .args(["cache", "clean"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 1 cached archive(s)"));
+
+    assert_eq!(fs::read_to_string(cache_dir.join("unrelated/important.txt")).unwrap(), "keep");
+    assert!(!cache_dir.join("github").exists());
+}
+
+#[test]
+fn test_cache_ignores_broken_config() {
+    let temp = tempdir().unwrap();
+    let cache_dir = temp.path().join("cache");
+    seed_hello_archive(&cache_dir, 0);
+    fs::write(temp.path().join("shared-kit.toml"), "[templates\n").unwrap();
+
+    shared_kit(&cache_dir)
+        .current_dir(temp.path())
+        .args(["cache", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("github/octocat/hello/default"));
+
+    shared_kit(&cache_dir)
+        .current_dir(temp.path())
+        .args(["cache", "prune", "--older-than", &u64::MAX.to_string()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 0 cached archive(s)"));
+}
+
+#[test]
+fn test_pin_then_verify_checksum() {
+    let temp = tempdir().unwrap();
+    let cache_dir = temp.path().join("cache");
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    seed_hello_archive(&cache_dir, now);
+    let config = temp.path().join("templates.toml");
+    fs::write(&config, "[templates.hello]\nkind = \"Project\"\nrepo = \"octocat/hello\"\n")
+        .unwrap();
+
+    shared_kit(&cache_dir)
+        .args(["template", "pin", "hello", "--config"])
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pinned 'hello' to sha256"));
+    assert!(fs::read_to_string(&config).unwrap().contains("sha256 = \""));
+
+    shared_kit(&cache_dir)
+        .current_dir(temp.path())
+        .args(["new", "app", "--template-name", "hello", "--offline", "--yes", "--config"])
+        .arg(&config)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Archive checksum verified"));
+
+    shared_kit(&cache_dir)
+        .current_dir(temp.path())
+        .args(["new", "other", "--repo", "octocat/hello", "--offline", "--checksum", "sha256:00ff"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Checksum mismatch"))
+        .stderr(predicate::str::contains("expected: 00ff"));
+    assert!(!temp.path().join("other/README.md").exists());
+}
+
+/// Answers every request with 404 and records the requests it received.
+fn serve_not_found() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(vec![]));
+
+    let received = Arc::clone(&requests);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut head = String::new();
+            let mut reader = BufReader::new(&stream);
+            while reader.read_line(&mut head).unwrap() > 2 {}
+            received.lock().unwrap().push(head.to_lowercase());
+            stream.write_all(b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n").unwrap();
+        }
+    });
+
+    (base, requests)
+}
+
+#[test]
+fn test_env_token_only_sent_to_listed_hosts() {
+    let temp = tempdir().unwrap();
+    let (proxy, requests) = serve_not_found();
+    let new_app = |name: &str| {
+        shared_kit(&temp.path().join("cache"))
+            .current_dir(temp.path())
+            .env("GITEA_TOKEN", "env-secret")
+            .args(["new", name, "--repo", "http://gitea.attacker.example/team/app#main"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("env-secret").not());
+    };
+
+    fs::write(
+        temp.path().join("shared-kit.toml"),
+        format!("[network]\nproxy = \"{}\"\nretries = 0\n", proxy),
+    )
+    .unwrap();
+    new_app("unlisted");
+
+    fs::write(
+        temp.path().join("shared-kit.toml"),
+        format!(
+            "[network]\nproxy = \"{}\"\nretries = 0\n\n[hosts]\n\"gitea.attacker.example\" = \"gitea\"\n",
+            proxy
+        ),
+    )
+    .unwrap();
+    new_app("listed");
+
+    let requests = requests.lock().unwrap();
+    let (unlisted, listed): (Vec<_>, Vec<_>) =
+        requests.iter().partition(|r| !r.contains("authorization"));
+    assert!(!unlisted.is_empty() && !listed.is_empty());
+    assert!(listed.iter().all(|r| r.contains("authorization: token env-secret")));
+}
@@ -1,10 +1,12 @@
 use predicates::prelude::*;
 use shared_kit_cli::config::{Config, ConfigMetadata};
-use shared_kit_cli::constant::{TemplateItem, TemplateKind, Templates};
+use shared_kit_cli::constant::{
+    ConditionalPaths, TemplateItem, TemplateKind, TemplateVar, Templates,
+};
 use shared_kit_cli::subcommand::new_command::{NewCommand, new_command_action};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tempfile::tempdir;
 
 fn dummy_config_with_template(template_path: PathBuf) -> Config {
@@ -16,10 +18,15 @@ fn dummy_config_with_template(template_path: PathBuf) -> Config {
             kind: TemplateKind::Project,
             template: Some(template_path.to_string_lossy().to_string()),
             repo: None,
+            ..Default::default()
         },
     );
 
-    Config { metadata: ConfigMetadata { templates: map }, current_config_path: None }
+    Config {
+        metadata: ConfigMetadata { templates: map, ..Default::default() },
+        current_config_path: None,
+        ..Default::default()
+    }
 }
 
 #[test]
@@ -35,6 +42,7 @@ fn test_successful_local_template_copy() {
         template: Some(template.to_string_lossy().into_owned()),
         repo: None,
         config: None,
+        ..Default::default()
     };
 
     let mut config = Config::default();
@@ -55,6 +63,7 @@ fn test_nonexistent_template_path() {
         template: Some(fake_path.to_string_lossy().into_owned()),
         repo: None,
         config: None,
+        ..Default::default()
     };
 
     let mut config = Config::default();
@@ -73,6 +82,7 @@ fn test_direct_repo_todo_path() {
         template: None,
         repo: Some("https://github.com/some/repo.git".to_string()),
         config: None,
+        ..Default::default()
     };
 
     let mut config = Config::default();
@@ -104,6 +114,7 @@ fn test_template_from_config_selection() {
         template: None,
         repo: None,
         config: None,
+        ..Default::default()
     };
 
     std::env::set_current_dir(temp.path()).unwrap();
@@ -114,7 +125,7 @@ fn test_template_from_config_selection() {
         .arg(&args.name)
         .arg("--config")
         .arg(config_path)
-        .write_stdin("test-template")
+        .args(["--template-name", "test-template"])
         .assert()
         .success()
         .stdout(predicate::str::contains("via_config"))
@@ -149,6 +160,7 @@ fn test_config_empty_should_fail() {
         template: None,
         repo: None,
         config: None,
+        ..Default::default()
     };
 
     std::env::set_current_dir(temp.path()).unwrap();
@@ -156,3 +168,542 @@ fn test_config_empty_should_fail() {
     assert!(result.is_err());
     assert!(format!("{}", result.unwrap_err()).contains("No templates found"));
 }
+
+#[test]
+fn test_template_vars_replaced_from_config() {
+    let temp = tempdir().unwrap();
+    let template = temp.path().join("tpl_vars");
+    fs::create_dir_all(template.join("src")).unwrap();
+    fs::write(template.join("package.json"), r#"{ "name": "{{project_name}}" }"#).unwrap();
+    fs::write(template.join("src/main.txt"), "{{project_name}} by {{author}}").unwrap();
+
+    let mut config = dummy_config_with_template(template.clone());
+    let item = config.metadata.templates.get_mut("test-template").unwrap();
+    item.template_vars = vec![
+        TemplateVar {
+            placeholder: "{{project_name}}".to_string(),
+            prompt: Some("Project name".to_string()),
+            default: Some("new_project".to_string()),
+            ..Default::default()
+        },
+        TemplateVar {
+            placeholder: "{{author}}".to_string(),
+            prompt: None,
+            default: Some("anonymous".to_string()),
+            ..Default::default()
+        },
+    ];
+
+    let config_path = temp.path().join("metadata.toml");
+    fs::write(&config_path, toml::to_string(&config.metadata).unwrap()).unwrap();
+
+    let mut cmd = assert_cmd::Command::cargo_bin("shared-kit").unwrap();
+    cmd.current_dir(temp.path())
+        .arg("new")
+        .arg("vars_app")
+        .arg("--config")
+        .arg(&config_path)
+        .args(["--template-name", "test-template", "--var", "project_name=my-app", "--yes"])
+        .assert()
+        .success();
+
+    let output_dir = temp.path().join("vars_app");
+    assert_eq!(
+        fs::read_to_string(output_dir.join("package.json")).unwrap(),
+        r#"{ "name": "my-app" }"#
+    );
+    assert_eq!(fs::read_to_string(output_dir.join("src/main.txt")).unwrap(), "my-app by anonymous");
+}
+
+#[test]
+fn test_template_includes_excludes_filter() {
+    let temp = tempdir().unwrap();
+    let template = temp.path().join("tpl_filter");
+    fs::create_dir_all(template.join("src")).unwrap();
+    fs::create_dir_all(template.join("node_modules/pkg")).unwrap();
+    fs::write(template.join("src/index.ts"), "export {}").unwrap();
+    fs::write(template.join("src/.index.ts.swp"), "swap").unwrap();
+    fs::write(template.join("node_modules/pkg/index.js"), "module").unwrap();
+    fs::write(template.join("package.json"), "{}").unwrap();
+    fs::write(template.join("notes.txt"), "notes").unwrap();
+
+    let mut config = dummy_config_with_template(template.clone());
+    let item = config.metadata.templates.get_mut("test-template").unwrap();
+    item.includes = vec!["/src".to_string(), "package.json".to_string()];
+    item.excludes = vec!["/node_modules".to_string(), "regex:\\.swp$".to_string()];
+
+    let config_path = temp.path().join("metadata.toml");
+    fs::write(&config_path, toml::to_string(&config.metadata).unwrap()).unwrap();
+
+    let mut cmd = assert_cmd::Command::cargo_bin("shared-kit").unwrap();
+    cmd.current_dir(temp.path())
+        .arg("new")
+        .arg("filtered_app")
+        .arg("--config")
+        .arg(&config_path)
+        .args(["--template-name", "test-template"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(2 files)"));
+
+    let output_dir = temp.path().join("filtered_app");
+    assert!(output_dir.join("src/index.ts").exists());
+    assert!(output_dir.join("package.json").exists());
+    assert!(!output_dir.join("src/.index.ts.swp").exists());
+    assert!(!output_dir.join("node_modules").exists());
+    assert!(!output_dir.join("notes.txt").exists());
+}
+
+#[test]
+fn test_relative_template_resolves_against_config_dir() {
+    let temp = tempdir().unwrap();
+    let config_dir = temp.path().join("configs");
+    let run_dir = temp.path().join("elsewhere");
+    fs::create_dir_all(config_dir.join("templates/basic")).unwrap();
+    fs::create_dir_all(&run_dir).unwrap();
+    fs::write(config_dir.join("templates/basic/hello.txt"), "relative").unwrap();
+
+    let config = dummy_config_with_template(PathBuf::from("./templates/basic"));
+    let config_path = config_dir.join("metadata.toml");
+    fs::write(&config_path, toml::to_string(&config.metadata).unwrap()).unwrap();
+
+    let mut cmd = assert_cmd::Command::cargo_bin("shared-kit").unwrap();
+    cmd.current_dir(&run_dir)
+        .arg("new")
+        .arg("relative_app")
+        .arg("--config")
+        .arg(&config_path)
+        .args(["--template-name", "test-template"])
+        .assert()
+        .success();
+
+    let output_file = run_dir.join("relative_app/hello.txt");
+    assert_eq!(fs::read_to_string(output_file).unwrap(), "relative");
+}
+
+fn write_vars_config(temp: &Path) -> PathBuf {
+    let template = temp.join("tpl_ci");
+    fs::create_dir_all(&template).unwrap();
+    fs::write(template.join("README.md"), "# {{project_name}} ({{license}})").unwrap();
+
+    let mut config = dummy_config_with_template(template);
+    let item = config.metadata.templates.get_mut("test-template").unwrap();
+    item.template_vars = vec![
+        TemplateVar { placeholder: "{{project_name}}".to_string(), ..Default::default() },
+        TemplateVar {
+            placeholder: "{{license}}".to_string(),
+            default: Some("MIT".to_string()),
+            ..Default::default()
+        },
+    ];
+
+    let config_path = temp.join("metadata.toml");
+    fs::write(&config_path, toml::to_string(&config.metadata).unwrap()).unwrap();
+    config_path
+}
+
+/// Writes a config whose `test-template` uses `template`, followed by `vars`, a TOML snippet of
+/// `[[templates.test-template.template_vars]]` tables.
+fn write_template_config(temp: &Path, template: &Path, vars: &str) -> PathBuf {
+    let template = toml::Value::String(template.to_string_lossy().into_owned());
+    let config =
+        format!("[templates.test-template]\nkind = \"Project\"\ntemplate = {}\n{}", template, vars);
+    let config_path = temp.join("metadata.toml");
+    fs::write(&config_path, config).unwrap();
+    config_path
+}
+
+/// `shared-kit new <name>` in `temp` with the `test-template` of `config`, `--yes` and every
+/// entry of `vars` passed as `--var`.
+fn shared_kit_new(temp: &Path, config: &Path, name: &str, vars: &[&str]) -> assert_cmd::Command {
+    let mut cmd = assert_cmd::Command::cargo_bin("shared-kit").unwrap();
+    cmd.current_dir(temp)
+        .args(["new", name, "--template-name", "test-template", "--yes", "--config"])
+        .arg(config);
+    for var in vars {
+        cmd.args(["--var", var]);
+    }
+    cmd
+}
+
+fn run_new(temp: &Path, config: &Path, name: &str, vars: &[&str]) -> assert_cmd::assert::Assert {
+    shared_kit_new(temp, config, name, vars).assert()
+}
+
+#[test]
+fn test_non_interactive_missing_var_should_fail() {
+    let temp = tempdir().unwrap();
+    let config_path = write_vars_config(temp.path());
+
+    run_new(temp.path(), &config_path, "ci_app", &[])
+        .failure()
+        .stderr(predicate::str::contains("Missing value for template variable 'project_name'"));
+}
+
+#[test]
+fn test_non_interactive_missing_template_name_should_fail() {
+    let temp = tempdir().unwrap();
+    let config_path = write_vars_config(temp.path());
+
+    let mut cmd = assert_cmd::Command::cargo_bin("shared-kit").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["new", "ci_app", "--config"])
+        .arg(&config_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--template-name"));
+}
+
+#[test]
+fn test_non_interactive_answers_file_and_var_override() {
+    let temp = tempdir().unwrap();
+    let config_path = write_vars_config(temp.path());
+    let answers = temp.path().join("answers.toml");
+    fs::write(&answers, "project_name = \"from-answers\"\nlicense = \"Apache-2.0\"\n").unwrap();
+
+    let mut cmd = assert_cmd::Command::cargo_bin("shared-kit").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["new", "ci_app", "--template-name", "test-template", "--answers"])
+        .arg(&answers)
+        .args(["--var", "project_name=from-flag", "--config"])
+        .arg(&config_path)
+        .assert()
+        .success();
+
+    let readme = fs::read_to_string(temp.path().join("ci_app/README.md")).unwrap();
+    assert_eq!(readme, "# from-flag (Apache-2.0)");
+}
+
+#[test]
+fn test_on_exists_actions() {
+    let temp = tempdir().unwrap();
+    let template = temp.path().join("tpl_exists");
+    fs::create_dir_all(&template).unwrap();
+    fs::write(template.join("new.txt"), "new").unwrap();
+    let target = temp.path().join("exists_app");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(target.join("old.txt"), "old").unwrap();
+
+    let run = |action: Option<&str>| {
+        let mut cmd = assert_cmd::Command::cargo_bin("shared-kit").unwrap();
+        cmd.current_dir(temp.path()).args(["new", "exists_app", "--template"]).arg(&template);
+        if let Some(action) = action {
+            cmd.args(["--on-exists", action]);
+        }
+        cmd.assert()
+    };
+
+    run(None).failure().stderr(predicate::str::contains("--on-exists"));
+    run(Some("fail")).failure().stderr(predicate::str::contains("already exists"));
+
+    run(Some("merge")).success();
+    assert!(target.join("old.txt").exists());
+    assert!(target.join("new.txt").exists());
+
+    run(Some("overwrite")).success();
+    assert!(!target.join("old.txt").exists());
+    assert!(target.join("new.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_failed_overwrite_keeps_existing_directory() {
+    let temp = tempdir().unwrap();
+    let template = temp.path().join("tpl_broken");
+    fs::create_dir_all(&template).unwrap();
+    fs::write(template.join("new.txt"), "new").unwrap();
+    std::os::unix::fs::symlink("../secret.txt", template.join("secret.txt")).unwrap();
+    let target = temp.path().join("exists_app");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(target.join("old.txt"), "old").unwrap();
+
+    let mut cmd = assert_cmd::Command::cargo_bin("shared-kit").unwrap();
+    cmd.current_dir(temp.path())
+        .args(["new", "exists_app", "--on-exists", "overwrite", "--template"])
+        .arg(&template)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to copy symlink"));
+
+    assert_eq!(fs::read_to_string(target.join("old.txt")).unwrap(), "old");
+    assert!(!target.join("new.txt").exists());
+    assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 2);
+}
+
+#[test]
+fn test_project_name_must_be_a_single_directory() {
+    let temp = tempdir().unwrap();
+    let template = temp.path().join("tpl_names");
+    fs::create_dir_all(&template).unwrap();
+    fs::write(template.join("new.txt"), "new").unwrap();
+    let workdir = temp.path().join("work");
+    fs::create_dir_all(&workdir).unwrap();
+
+    for name in [".", "..", "../escape", "nested/app", "/tmp/app"] {
+        let mut cmd = assert_cmd::Command::cargo_bin("shared-kit").unwrap();
+        cmd.current_dir(&workdir)
+            .args(["new", name, "--on-exists", "overwrite", "--template"])
+            .arg(&template)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid project name"));
+    }
+
+    assert!(workdir.exists());
+    assert!(!temp.path().join("escape").exists());
+}
+
+#[test]
+fn test_binary_files_are_copied_unchanged() {
+    let temp = tempdir().unwrap();
+    let config_path = write_vars_config(temp.path());
+    let template = temp.path().join("tpl_ci");
+    let png: &[u8] = &[0x89, b'P', b'N', b'G', 0x00, 0xFF, b'{', b'{'];
+    fs::write(template.join("logo.png"), png).unwrap();
+    fs::write(template.join("excluded.ico"), [0x00, 0xFE, 0xFF]).unwrap();
+    fs::write(template.join("raw.txt"), "{{project_name}}").unwrap();
+
+    let mut metadata: ConfigMetadata =
+        toml::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+    let item = metadata.templates.get_mut("test-template").unwrap();
+    item.excludes = vec!["excluded.ico".to_string()];
+    item.binary = vec!["*.txt".to_string()];
+    fs::write(&config_path, toml::to_string(&metadata).unwrap()).unwrap();
+
+    run_new(temp.path(), &config_path, "bin_app", &["project_name=demo"]).success();
+
+    let out = temp.path().join("bin_app");
+    assert_eq!(fs::read(out.join("logo.png")).unwrap(), png);
+    assert_eq!(fs::read_to_string(out.join("raw.txt")).unwrap(), "{{project_name}}");
+    assert_eq!(fs::read_to_string(out.join("README.md")).unwrap(), "# demo (MIT)");
+    assert!(!out.join("excluded.ico").exists());
+}
+
+#[test]
+fn test_template_engine_conditionals_filters_and_errors() {
+    let temp = tempdir().unwrap();
+    let config_path = write_vars_config(temp.path());
+    let template = temp.path().join("tpl_ci");
+    fs::write(
+        template.join("index.txt"),
+        "{% if license == 'MIT' %}\nmit\n{% else %}\nother\n{% endif %}\n{{ project_name | snake_case }}\n",
+    )
+    .unwrap();
+    let django = "{% if user.is_authenticated %}\n<p>{{ user.name }}</p>\n{% endif %}\n\
+        {% for item in items %}{{ item }}{% endfor %}\n";
+    fs::write(template.join("base.html"), django).unwrap();
+
+    run_new(temp.path(), &config_path, "engine_app", &["project_name=My App"]).success();
+    let rendered = fs::read_to_string(temp.path().join("engine_app/index.txt")).unwrap();
+    assert_eq!(rendered, "mit\nmy_app\n");
+    let copied = fs::read_to_string(temp.path().join("engine_app/base.html")).unwrap();
+    assert_eq!(copied, django);
+
+    fs::write(template.join("index.txt"), "ok\n{% for x in license %}\n").unwrap();
+    run_new(temp.path(), &config_path, "broken_app", &["project_name=demo"])
+        .failure()
+        .stderr(predicate::str::contains("index.txt:2: unclosed {% for %} block"));
+}
+
+#[test]
+fn test_templated_file_and_directory_names() {
+    let temp = tempdir().unwrap();
+    let config_path = write_vars_config(temp.path());
+    let template = temp.path().join("tpl_ci");
+    fs::create_dir_all(template.join("src/{{ project_name | snake_case }}")).unwrap();
+    fs::write(template.join("src/{{ project_name | snake_case }}/lib.rs"), "// {{project_name}}")
+        .unwrap();
+    fs::write(template.join("__license__.txt"), "license").unwrap();
+
+    run_new(temp.path(), &config_path, "names_app", &["project_name=My App"]).success();
+
+    let out = temp.path().join("names_app");
+    assert_eq!(fs::read_to_string(out.join("src/my_app/lib.rs")).unwrap(), "// My App");
+    assert!(out.join("MIT.txt").exists());
+
+    fs::write(template.join("MIT.txt"), "clash").unwrap();
+    run_new(temp.path(), &config_path, "clash_app", &["project_name=demo"])
+        .failure()
+        .stderr(predicate::str::contains("Name collision"));
+}
+
+#[test]
+fn test_conditional_paths_follow_answers() {
+    let temp = tempdir().unwrap();
+    let config_path = write_vars_config(temp.path());
+    let template = temp.path().join("tpl_ci");
+    fs::write(template.join("Dockerfile"), "FROM rust").unwrap();
+    fs::create_dir_all(template.join(".github/workflows")).unwrap();
+    fs::write(template.join(".github/workflows/ci.yml"), "on: push").unwrap();
+
+    let mut metadata: ConfigMetadata =
+        toml::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+    let item = metadata.templates.get_mut("test-template").unwrap();
+    item.template_vars.push(TemplateVar {
+        placeholder: "{{docker}}".to_string(),
+        default: Some("false".to_string()),
+        ..Default::default()
+    });
+    item.conditional_paths = vec![
+        ConditionalPaths { paths: vec!["Dockerfile".to_string()], when: "docker == true".into() },
+        ConditionalPaths { paths: vec!["/.github".to_string()], when: "license != 'MIT'".into() },
+    ];
+    fs::write(&config_path, toml::to_string(&metadata).unwrap()).unwrap();
+
+    let run = |name: &str, docker: &str| {
+        run_new(temp.path(), &config_path, name, &["project_name=demo", docker])
+    };
+
+    run("plain_app", "docker=false").success().stdout(predicate::str::contains("(1 files)"));
+    assert!(temp.path().join("plain_app/README.md").exists());
+    assert!(!temp.path().join("plain_app/Dockerfile").exists());
+    assert!(!temp.path().join("plain_app/.github").exists());
+
+    run("docker_app", "docker=true").success().stdout(predicate::str::contains("(2 files)"));
+    assert!(temp.path().join("docker_app/Dockerfile").exists());
+
+    metadata.templates.get_mut("test-template").unwrap().conditional_paths[0].when =
+        "dockr == true".into();
+    fs::write(&config_path, toml::to_string(&metadata).unwrap()).unwrap();
+    run("broken_app", "docker=true")
+        .failure()
+        .stderr(predicate::str::contains("Invalid condition 'dockr == true'"));
+}
+
+fn write_typed_vars_config(temp: &Path) -> PathBuf {
+    let template = temp.join("tpl_typed");
+    fs::create_dir_all(&template).unwrap();
+    fs::write(
+        template.join("out.txt"),
+        "{{ name }}|{{ docker }}|{{ port }}|{{ db }}|{% for f in features %}{{ f }};{% endfor %}",
+    )
+    .unwrap();
+
+    let vars = r#"
+[[templates.test-template.template_vars]]
+placeholder = "{{name}}"
+validate = "^[a-z][a-z0-9-]*$"
+validate_message = "use lowercase letters, digits and dashes"
+
+[[templates.test-template.template_vars]]
+placeholder = "{{docker}}"
+type = "bool"
+default = "no"
+
+[[templates.test-template.template_vars]]
+placeholder = "{{port}}"
+type = "integer"
+when = "docker"
+
+[[templates.test-template.template_vars]]
+placeholder = "{{db}}"
+type = "select"
+choices = ["postgres", "sqlite"]
+default = "sqlite"
+
+[[templates.test-template.template_vars]]
+placeholder = "{{features}}"
+type = "multiselect"
+choices = ["router", "store", "i18n"]
+default = "router"
+"#;
+    write_template_config(temp, &template, vars)
+}
+
+#[test]
+fn test_typed_template_vars_from_flags() {
+    let temp = tempdir().unwrap();
+    let config_path = write_typed_vars_config(temp.path());
+    let run = |name: &str, vars: &[&str]| run_new(temp.path(), &config_path, name, vars);
+
+    run("defaults_app", &["name=demo"]).success();
+    let out = fs::read_to_string(temp.path().join("defaults_app/out.txt")).unwrap();
+    assert_eq!(out, "demo|false||sqlite|router;");
+
+    run(
+        "flags_app",
+        &["name=demo", "docker=yes", "port=8080", "db=postgres", "features=store, i18n"],
+    )
+    .success();
+    let out = fs::read_to_string(temp.path().join("flags_app/out.txt")).unwrap();
+    assert_eq!(out, "demo|true|8080|postgres|store;i18n;");
+
+    let invalid = [
+        ("name=Demo", "use lowercase letters, digits and dashes"),
+        ("docker=maybe", "expected true or false"),
+        ("db=mysql", "expected one of: postgres, sqlite"),
+        ("features=router,admin", "expected one of: router, store, i18n"),
+    ];
+    for (var, message) in invalid {
+        let mut vars = vec!["name=demo", var];
+        if var.starts_with("name=") {
+            vars.remove(0);
+        }
+        run("invalid_app", &vars).failure().stderr(predicate::str::contains(message));
+    }
+
+    run("port_app", &["name=demo", "docker=true", "port=80a"])
+        .failure()
+        .stderr(predicate::str::contains("Invalid value '80a' for template variable 'port'"));
+}
+
+#[test]
+fn test_computed_template_vars() {
+    let temp = tempdir().unwrap();
+    let template = temp.path().join("tpl_computed");
+    fs::create_dir_all(&template).unwrap();
+    fs::write(template.join("out.txt"), "{{ crate_name }}|{{ author }}|{{ year }}|{{ id }}")
+        .unwrap();
+
+    let vars = r#"
+[[templates.test-template.template_vars]]
+placeholder = "{{crate_name}}"
+compute = "project_name | snake_case"
+
+[[templates.test-template.template_vars]]
+placeholder = "{{project_name}}"
+
+[[templates.test-template.template_vars]]
+placeholder = "{{author}}"
+compute = "env:SHARED_KIT_TEST_AUTHOR"
+
+[[templates.test-template.template_vars]]
+placeholder = "{{year}}"
+compute = "now:%Y"
+
+[[templates.test-template.template_vars]]
+placeholder = "{{id}}"
+compute = "uuid"
+"#;
+    let config_path = write_template_config(temp.path(), &template, vars);
+
+    let run = |name: &str, vars: &[&str]| {
+        shared_kit_new(temp.path(), &config_path, name, vars)
+            .env("SHARED_KIT_TEST_AUTHOR", "Jane")
+            .assert()
+    };
+
+    run("computed_app", &["project_name=My App"]).success();
+    let out = fs::read_to_string(temp.path().join("computed_app/out.txt")).unwrap();
+    let parts: Vec<&str> = out.split('|').collect();
+    assert_eq!(parts[..2], ["my_app", "Jane"]);
+    assert_eq!(parts[2].len(), 4);
+    assert!(parts[2].chars().all(|c| c.is_ascii_digit()));
+    assert_eq!(parts[3].len(), 36);
+    assert_eq!(parts[3].as_bytes()[14], b'4');
+
+    run("override_app", &["project_name=My App", "crate_name=custom", "id=fixed"]).success();
+    let out = fs::read_to_string(temp.path().join("override_app/out.txt")).unwrap();
+    assert!(out.starts_with("custom|Jane|"));
+    assert!(out.ends_with("|fixed"));
+
+    let mut config = fs::read_to_string(&config_path).unwrap();
+    config.push_str(
+        "\n[[templates.test-template.template_vars]]\nplaceholder = \"{{license}}\"\nwhen = \"author\"\n",
+    );
+    fs::write(&config_path, config).unwrap();
+    run("when_app", &["project_name=My App", "license=MIT"])
+        .failure()
+        .stderr(predicate::str::contains("Invalid condition 'author' for 'license'"))
+        .stderr(predicate::str::contains("'author' is evaluated after it"));
+}
@@ -0,0 +1,145 @@
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+fn shared_kit() -> assert_cmd::Command {
+    assert_cmd::Command::cargo_bin("shared-kit").unwrap()
+}
+
+#[test]
+fn test_template_add_list_show_remove() {
+    let temp = tempdir().unwrap();
+    let config_path = temp.path().join("metadata.toml");
+    fs::create_dir_all(temp.path().join("templates/basic")).unwrap();
+    fs::write(&config_path, "# shared templates\n").unwrap();
+
+    shared_kit()
+        .current_dir(temp.path())
+        .args(["template", "add", "basic", "--path", "templates/basic", "--kind", "project"])
+        .arg("--config")
+        .arg(&config_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added template 'basic'"));
+
+    shared_kit()
+        .current_dir(temp.path())
+        .args(["template", "add", "hello", "--repo", "octocat/Hello-World#master"])
+        .arg("--config")
+        .arg(&config_path)
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&config_path).unwrap();
+    assert!(content.starts_with("# shared templates\n"));
+    assert!(content.contains("template = \"./templates/basic\""));
+
+    shared_kit()
+        .args(["template", "list", "--kind", "project", "--config"])
+        .arg(&config_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("basic"))
+        .stdout(predicate::str::contains("hello").not());
+
+    shared_kit()
+        .args(["template", "show", "hello", "--config"])
+        .arg(&config_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("repo: octocat/Hello-World#master"))
+        .stdout(predicate::str::contains("package"));
+
+    shared_kit()
+        .args(["template", "remove", "basic", "--config"])
+        .arg(&config_path)
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&config_path).unwrap();
+    assert!(!content.contains("[templates.basic]"));
+    assert!(content.contains("[templates.hello]"));
+}
+
+#[test]
+fn test_template_add_with_relative_config_then_generate() {
+    let temp = tempdir().unwrap();
+    let work = temp.path().join("work");
+    let outside = temp.path().join("outside");
+    fs::create_dir_all(work.join("templates/basic")).unwrap();
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(work.join("templates/basic/basic.txt"), "basic").unwrap();
+    fs::write(outside.join("outside.txt"), "outside").unwrap();
+
+    shared_kit()
+        .current_dir(&work)
+        .args(["template", "add", "basic", "--path", "templates/basic", "-c", "d.json"])
+        .assert()
+        .success();
+    shared_kit()
+        .current_dir(&work)
+        .args(["template", "add", "outside", "--path"])
+        .arg(&outside)
+        .args(["-c", "d.json"])
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(work.join("d.json")).unwrap();
+    assert!(content.contains(r#""template": "./templates/basic""#), "{}", content);
+    assert!(!content.contains("null"), "{}", content);
+    let outside_entry = serde_json::to_string(&outside.display().to_string()).unwrap();
+    assert!(content.contains(&format!(r#""template": {}"#, outside_entry)), "{}", content);
+
+    for name in ["basic", "outside"] {
+        shared_kit()
+            .current_dir(&work)
+            .args(["new", &format!("{}_app", name), "-n", name, "-c", "d.json"])
+            .assert()
+            .success();
+        let file = work.join(format!("{0}_app/{0}.txt", name));
+        assert_eq!(fs::read_to_string(file).unwrap(), name);
+    }
+}
+
+#[test]
+fn test_template_add_existing_requires_force() {
+    let temp = tempdir().unwrap();
+    let config_path = temp.path().join("metadata.json");
+
+    shared_kit()
+        .args(["template", "add", "hello", "--repo", "octocat/Hello-World", "--config"])
+        .arg(&config_path)
+        .assert()
+        .success();
+
+    shared_kit()
+        .args(["template", "add", "hello", "--repo", "octocat/Other", "--config"])
+        .arg(&config_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+    assert!(!fs::read_to_string(&config_path).unwrap().contains("octocat/Other"));
+
+    shared_kit()
+        .args(["template", "add", "hello", "--repo", "octocat/Other", "--force", "--config"])
+        .arg(&config_path)
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&config_path).unwrap();
+    assert!(content.contains("octocat/Other"));
+}
+
+#[test]
+fn test_template_show_unknown_should_fail() {
+    let temp = tempdir().unwrap();
+    let config_path = temp.path().join("metadata.toml");
+    fs::write(&config_path, "").unwrap();
+
+    shared_kit()
+        .args(["template", "show", "missing", "--config"])
+        .arg(&config_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Template 'missing' not found"));
+}